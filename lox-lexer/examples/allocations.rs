@@ -0,0 +1,47 @@
+//! Counts the heap allocations done while lexing a large source,
+//! with and without an arena for the text payloads.
+//!
+//! cargo run --release --example allocations --features arena
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bumpalo::Bump;
+use lox_lexer::*;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn main() {
+    let snippet = "var language = \"lox\"; // a comment\nfun add(a, b) { return a + b * 12.5; }\n";
+    let source = snippet.repeat(10_000);
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let tokens = Lexer::with_source(&source).count();
+    let owned = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    let arena = Bump::new();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let arena_tokens = Lexer::with_source_in(&source, &arena).count();
+    let in_arena = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    assert_eq!(tokens, arena_tokens);
+    println!("tokens: {}", tokens);
+    println!("allocations without arena: {}", owned);
+    println!("allocations with arena: {}", in_arena);
+}
@@ -0,0 +1,26 @@
+//! Compares the time needed to classify the tokens of a large source
+//! with the time needed to fully tokenize it.
+//!
+//! cargo run --release --example scan_kinds
+
+use std::time::Instant;
+
+use lox_lexer::*;
+
+fn main() {
+    let snippet = "var language = \"lox\"; // a comment\nfun add(a, b) { return a + b * 12.5; }\n";
+    let source = snippet.repeat(100_000);
+
+    let start = Instant::now();
+    let tokens: Vec<Token> = Lexer::with_source(&source).collect();
+    let tokenize = start.elapsed();
+
+    let start = Instant::now();
+    let kinds = Lexer::scan_kinds(&source).count();
+    let scan = start.elapsed();
+
+    assert_eq!(tokens.len(), kinds);
+    println!("tokens: {}", kinds);
+    println!("tokenize: {:?}", tokenize);
+    println!("scan_kinds: {:?}", scan);
+}
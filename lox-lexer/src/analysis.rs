@@ -0,0 +1,679 @@
+use std::borrow::Borrow;
+use std::hash::{Hash, Hasher};
+
+use crate::chars::*;
+use crate::{Lexeme, Lexer, LexerIter, Token};
+
+/// The number of indented lines sampled when detecting the indentation.
+const INDENT_SAMPLE_LINES: usize = 10;
+
+/// The indentation style of a source.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IndentStyle {
+    /// The lines are indented with tabs
+    Tabs,
+    /// The lines are indented with the given number of spaces
+    Spaces(usize),
+    /// The lines are indented with both tabs and spaces
+    Mixed,
+    /// No indented line was found
+    Unknown,
+}
+
+/// Returns the greatest common divisor of two numbers.
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Detects the indentation style from the whitespaces which start the
+/// first indented lines of the token stream. Blank lines are ignored.
+pub fn detect_indent(tokens: &[Token]) -> IndentStyle {
+    let indents = tokens
+        .windows(2)
+        .filter(|w| w[0].span.start_col.0 == 0)
+        .filter(|w| !matches!(w[1].lexeme, Lexeme::NewLine | Lexeme::Eof))
+        .filter_map(|w| match &w[0].lexeme {
+            Lexeme::Whitespace(ws) => Some(ws),
+            _ => None,
+        })
+        .take(INDENT_SAMPLE_LINES);
+
+    let mut tabs = false;
+    let mut spaces = 0;
+
+    for ws in indents {
+        let has_tab = ws.contains(CHAR_TAB);
+        let has_space = ws.contains(CHAR_WHITESPACE);
+
+        match (has_tab, has_space) {
+            (true, true) => return IndentStyle::Mixed,
+            (true, false) => tabs = true,
+            (false, true) => spaces = gcd(spaces, ws.len()),
+            (false, false) => (),
+        }
+    }
+
+    match (tabs, spaces) {
+        (true, 0) => IndentStyle::Tabs,
+        (true, _) => IndentStyle::Mixed,
+        (false, 0) => IndentStyle::Unknown,
+        (false, n) => IndentStyle::Spaces(n),
+    }
+}
+
+/// Returns true for the lexemes which do not matter to a parser.
+pub(crate) fn is_trivia(lexeme: &Lexeme) -> bool {
+    matches!(
+        lexeme,
+        Lexeme::Whitespace(_)
+            | Lexeme::NewLine
+            | Lexeme::Comment { .. }
+            | Lexeme::Bof
+            | Lexeme::Eof
+    )
+}
+
+/// Returns the token under the cursor, given by a line (from 1) and a
+/// column (from 0), e.g. for hover or go-to-definition queries.
+///
+/// The cursor sits between two characters, so at a token boundary it
+/// touches both the token which ends and the one which starts there:
+/// - the token starting at the cursor is preferred, as [Span::contains]
+///   excludes the end of a span;
+/// - unless it is trivia (whitespace, new line, comment) and the token
+///   ending at the cursor is significant, e.g. `x| ` returns `x`.
+///
+/// A cursor right after the last character of the source returns the
+/// last significant token ending there, if any.
+///
+/// [Span::contains]: crate::Span::contains
+pub fn token_at(tokens: &[Token], line: usize, col: usize) -> Option<&Token> {
+    let ends_at = |tkn: &&Token| {
+        let span = tkn.span;
+        span.end_line.0 == line && span.end_col.0 == col && !is_trivia(&tkn.lexeme)
+    };
+
+    match tokens.iter().position(|tkn| tkn.span.contains(line, col)) {
+        Some(i) if is_trivia(&tokens[i].lexeme) => {
+            let start = tokens[i].span;
+            let at_start = start.start_line.0 == line && start.start_col.0 == col;
+
+            match i.checked_sub(1).map(|j| &tokens[j]) {
+                Some(prev) if at_start && ends_at(&prev) => Some(prev),
+                _ => Some(&tokens[i]),
+            }
+        }
+        Some(i) => Some(&tokens[i]),
+        None => tokens.iter().rev().find(ends_at),
+    }
+}
+
+/// Returns the indices of the commas which are followed, ignoring the trivia,
+/// by a closing `)` or `}`, e.g. the second comma of `f(a, b,)`.
+pub fn trailing_commas(tokens: &[Token]) -> Vec<usize> {
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, tkn)| tkn.lexeme == Lexeme::Comma)
+        .filter(|(i, _)| {
+            let next = tokens[i + 1..].iter().find(|tkn| !is_trivia(&tkn.lexeme));
+            next.is_some_and(|tkn| matches!(tkn.lexeme, Lexeme::RightParen | Lexeme::RightBrace))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Reconstructs the source from the complete token stream, including the
+/// trivia. For a source lexed with the default options, the reconstruction
+/// is exact.
+///
+/// The strings are reconstructed with double quotes and with their escapes
+/// as they were kept by the lexer, so the strings with other delimiters or
+/// with decoded escapes are not reconstructed as in the source. The same
+/// goes for the raw identifiers, which lose their `r#` prefix, and for the
+/// interned identifiers, whose names are not in the tokens. An unterminated
+/// string gets no closing quote. The characters skipped by the lexer, e.g.
+/// an unexpected `@`, are not in any token, so they are missing.
+pub fn reconstruct(tokens: &[Token]) -> String {
+    let mut source = String::new();
+
+    for tkn in tokens {
+        push_source_text(&mut source, tkn);
+    }
+
+    source
+}
+
+/// Pushes the source text of a token, as it is reconstructed by [reconstruct].
+fn push_source_text(source: &mut String, tkn: &Token) {
+    match &tkn.lexeme {
+        Lexeme::Identifier(s) | Lexeme::Whitespace(s) => source.push_str(s),
+        Lexeme::String(s) => {
+            source.push(CHAR_DOUBLE_QUOTE);
+            source.push_str(s);
+            if tkn.closed == Some(true) {
+                source.push(CHAR_DOUBLE_QUOTE);
+            }
+        }
+        Lexeme::Number(number) => source.push_str(&number.raw),
+        Lexeme::Comment { text, style } => {
+            let (open, close) = style.delimiters();
+            source.push_str(open);
+            source.push_str(text);
+            source.push_str(close);
+        }
+        Lexeme::Regex(r) => {
+            source.push(CHAR_SLASH);
+            source.push_str(r);
+            source.push(CHAR_SLASH);
+        }
+        Lexeme::Heredoc { tag, body } => {
+            source.push_str("<<");
+            source.push_str(tag);
+            source.push(CHAR_NEWLINE);
+            source.push_str(body);
+            source.push_str(tag);
+        }
+        Lexeme::NewLine => source.push(CHAR_NEWLINE),
+        Lexeme::Symbol(_) | Lexeme::Bof | Lexeme::Eof => (),
+        l => source.push_str(&l.to_string()),
+    }
+}
+
+/// Returns a fingerprint of the token stream, e.g. as a build cache key.
+/// Only the significant lexemes are hashed, in order, so the trivia and
+/// the spans do not matter: a reformatting keeps the fingerprint.
+///
+/// The hash is the 64-bit FNV-1a of the lexemes, with the integers written
+/// as little-endian, so it is the same across runs and platforms.
+pub fn fingerprint(tokens: &[Token]) -> u64 {
+    hash_significant(tokens.iter())
+}
+
+/// The 64-bit FNV-1a hasher. Unlike the hasher of the standard library,
+/// its algorithm is fixed, and the integers are written as little-endian,
+/// the `usize` and `isize` ones widened to 64 bits.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    fn new() -> Self {
+        Fnv1a(Self::OFFSET_BASIS)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Hashes the significant lexemes of the tokens, in order.
+fn hash_significant<T: Borrow<Token>>(tokens: impl Iterator<Item = T>) -> u64 {
+    let mut hasher = Fnv1a::new();
+
+    tokens
+        .filter(|tkn| !is_trivia(&tkn.borrow().lexeme))
+        .for_each(|tkn| tkn.borrow().lexeme.hash(&mut hasher));
+
+    hasher.finish()
+}
+
+/// Removes the 'eof' tokens, except the last token, e.g. from the
+/// concatenated token streams of several sources, so a parser
+/// sees a well-formed stream with one 'eof' token at its end.
+pub fn strip_interior_eof(tokens: &mut Vec<Token>) {
+    let last = tokens.len().saturating_sub(1);
+    let mut i = 0;
+
+    tokens.retain(|tkn| {
+        let keep = i == last || tkn.lexeme != Lexeme::Eof;
+        i += 1;
+        keep
+    });
+}
+
+impl Lexer {
+    /// Returns the first significant token of a source, or `None` if the
+    /// source has only trivia, e.g. to detect the language of a file.
+    /// It stops scanning right after the token.
+    pub fn first_significant(src: &str) -> Option<Token> {
+        Lexer::with_source(src).find(|tkn| !is_trivia(&tkn.lexeme))
+    }
+}
+
+impl<'a> LexerIter<'a> {
+    /// Checks if the source has only whitespaces, new lines and comments,
+    /// e.g. to skip the empty modules. It stops at the first significant token.
+    pub fn is_trivia_only(mut self) -> bool {
+        self.all(|tkn| is_trivia(&tkn.lexeme))
+    }
+
+    /// Returns the hash of the significant tokens, the same as the [fingerprint]
+    /// of the collected tokens, without collecting them, e.g. as the cache key
+    /// of a compiled module which survives the whitespace-only edits.
+    pub fn significant_hash(self) -> u64 {
+        hash_significant(self)
+    }
+
+    /// Returns the source text of the significant tokens, separated by single
+    /// spaces, e.g. to strip the comments and normalize the whitespaces. The
+    /// tokens are rendered as by [reconstruct], e.g. the numbers keep their
+    /// raw spelling and the strings their quotes. The characters skipped by
+    /// the lexer, e.g. an unexpected `@`, are kept from the source as words.
+    pub fn significant_source(mut self) -> String {
+        let mut source = String::new();
+        let mut end = self.ctx.bytes_read;
+
+        fn separate(source: &mut String) {
+            if !source.is_empty() {
+                source.push(CHAR_WHITESPACE);
+            }
+        }
+
+        while let Some((skipped, tkn)) = self.next_with_skipped(&mut end) {
+            for word in skipped.split_whitespace() {
+                separate(&mut source);
+                source.push_str(word);
+            }
+
+            if !is_trivia(&tkn.lexeme) {
+                separate(&mut source);
+                push_source_text(&mut source, &tkn);
+            }
+        }
+
+        source
+    }
+
+    /// Returns an iterator over the tokens, each with its exact text in the
+    /// source, e.g. an identifier, a string with its quotes or a comment which
+    /// spans several lines. The text is empty for a lexer which was not created
+    /// from a string, and for the tokens pushed back before the call.
+    pub fn with_text(mut self) -> impl Iterator<Item = (Token, &'a str)> {
+        let pushed_back = std::mem::take(&mut self.pushback);
+
+        pushed_back
+            .into_iter()
+            .rev()
+            .map(|tkn| (tkn, ""))
+            .chain(std::iter::from_fn(move || {
+                let tkn = self.next()?;
+                let text = self
+                    .ctx
+                    .text
+                    .map_or("", |text| &text[self.ctx.token_start..self.ctx.bytes_read]);
+                Some((tkn, text))
+            }))
+    }
+
+    /// Returns an iterator over the significant tokens and the 'eof' token,
+    /// each with the source text of the trivia which precedes it, flattened
+    /// into one string, e.g. for a reformatter which keeps the comments
+    /// verbatim. Concatenating the gaps and the tokens gives back the source,
+    /// with the same limits as [reconstruct], except that the characters
+    /// skipped by the lexer, e.g. an unexpected `@`, are kept in the gaps.
+    pub fn collect_gaps(mut self) -> impl Iterator<Item = (String, Token)> + 'a {
+        let mut gap = String::new();
+        let mut end = self.ctx.bytes_read;
+
+        std::iter::from_fn(move || loop {
+            let (skipped, tkn) = self.next_with_skipped(&mut end)?;
+            gap.push_str(skipped);
+
+            if is_trivia(&tkn.lexeme) && tkn.lexeme != Lexeme::Eof {
+                push_source_text(&mut gap, &tkn);
+            } else {
+                return Some((std::mem::take(&mut gap), tkn));
+            }
+        })
+    }
+
+    /// Returns the next token with the source text skipped by the lexer right
+    /// before it, e.g. an unexpected `@`. The text is skipped from `end`, the
+    /// byte offset where the previous token ends, which is moved past the
+    /// token. It is empty for a lexer which was not created from a string.
+    fn next_with_skipped(&mut self, end: &mut usize) -> Option<(&'a str, Token)> {
+        let tkn = self.next()?;
+        let skipped = self
+            .ctx
+            .text
+            .and_then(|text| text.get(*end..self.ctx.token_start))
+            .unwrap_or_default();
+        *end = self.ctx.bytes_read;
+
+        Some((skipped, tkn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lexer;
+
+    fn indent_of(source: &str) -> IndentStyle {
+        let tokens: Vec<Token> = Lexer::with_source(source).collect();
+        detect_indent(&tokens)
+    }
+
+    #[test]
+    fn test_detect_indent_tabs() {
+        let source = "fun f() {\n\tif (x) {\n\t\tprint x;\n\t}\n}";
+        assert_eq!(IndentStyle::Tabs, indent_of(source));
+    }
+
+    #[test]
+    fn test_detect_indent_two_spaces() {
+        let source = "fun f() {\n  if (x) {\n    print x;\n  }\n}";
+        assert_eq!(IndentStyle::Spaces(2), indent_of(source));
+    }
+
+    #[test]
+    fn test_detect_indent_mixed() {
+        let source = "fun f() {\n\tif (x) {\n    print x;\n\t}\n}";
+        assert_eq!(IndentStyle::Mixed, indent_of(source));
+    }
+
+    #[test]
+    fn test_detect_indent_ignores_blank_lines() {
+        let source = "fun f() {\n\t\n  print x;\n}";
+        assert_eq!(IndentStyle::Spaces(2), indent_of(source));
+    }
+
+    #[test]
+    fn test_token_at() {
+        let tokens: Vec<Token> = Lexer::with_source("var ab = 1;\nprint ab;").collect();
+        let at = |line, col| token_at(&tokens, line, col).map(|tkn| &tkn.lexeme);
+
+        assert_eq!(Some(&Lexeme::Var), at(1, 0));
+        assert_eq!(Some(&Lexeme::Var), at(1, 2));
+        assert_eq!(Some(&Lexeme::Identifier("ab".to_string())), at(1, 5));
+        assert_eq!(Some(&Lexeme::Print), at(2, 0));
+        assert_eq!(None, at(3, 0));
+    }
+
+    #[test]
+    fn test_token_at_boundaries() {
+        let tokens: Vec<Token> = Lexer::with_source("x+y  // c\nz").collect();
+        let at = |line, col| token_at(&tokens, line, col).map(|tkn| &tkn.lexeme);
+
+        // the token starting at the cursor wins over the one ending there
+        assert_eq!(Some(&Lexeme::Plus), at(1, 1));
+        // unless it is trivia
+        assert_eq!(Some(&Lexeme::Identifier("y".to_string())), at(1, 3));
+        // inside trivia
+        assert_eq!(Some(&Lexeme::Whitespace("  ".to_string())), at(1, 4));
+        assert_eq!(Some(&Lexeme::line_comment(" c\n")), at(1, 5));
+        // at the end of the source
+        assert_eq!(Some(&Lexeme::Identifier("z".to_string())), at(2, 1));
+    }
+
+    #[test]
+    fn test_trailing_commas() {
+        let tokens: Vec<Token> = Lexer::with_source("f(a, b,)").collect();
+        assert_eq!(vec![6], trailing_commas(&tokens));
+    }
+
+    #[test]
+    fn test_trailing_commas_with_trivia() {
+        let tokens: Vec<Token> = Lexer::with_source("f(a, // one\n  b, // two\n)").collect();
+        let commas = trailing_commas(&tokens);
+
+        assert_eq!(1, commas.len());
+        assert_eq!(3, tokens[commas[0]].span.start_col.0);
+        assert_eq!(2, tokens[commas[0]].span.start_line.0);
+    }
+
+    #[test]
+    fn test_trailing_commas_none() {
+        let tokens: Vec<Token> = Lexer::with_source("f(a, b);,").collect();
+        assert!(trailing_commas(&tokens).is_empty());
+    }
+
+    #[test]
+    fn test_reconstruct() {
+        let sources = [
+            "",
+            "var x = 1;",
+            "var s = \"one\ntwo\";\nprint s;\n",
+            "// header\n\tif (a >= 1.50) {\r\n  \t print a != 007;\n}\n\n// end",
+            "fun f(a, b) { return a / b * -c; } // trailing",
+            "class A < B { init() { this.x = !true and nil or false; } }",
+            "  \t  \n\n   ",
+            "var s = \"abc",
+        ];
+
+        for source in sources.iter() {
+            let tokens: Vec<Token> = Lexer::with_source(source).collect();
+            assert_eq!(*source, reconstruct(&tokens));
+        }
+
+        // The unexpected characters are skipped, so they are not in any token.
+        let tokens: Vec<Token> = Lexer::with_source("a @ b").collect();
+        assert_eq!("a  b", reconstruct(&tokens));
+    }
+
+    fn fingerprint_of(source: &str) -> u64 {
+        let tokens: Vec<Token> = Lexer::with_source(source).collect();
+        fingerprint(&tokens)
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_formatting() {
+        let before = fingerprint_of("var x = 1;\nprint x;");
+        let after = fingerprint_of("var   x=1; // one\n\n\tprint x ;");
+
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn test_fingerprint_changes_on_rename() {
+        let before = fingerprint_of("var x = 1;\nprint x;");
+        let after = fingerprint_of("var y = 1;\nprint y;");
+
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn test_significant_hash() {
+        let hash = |source| Lexer::with_source(source).significant_hash();
+
+        assert_eq!(hash("var x = 1;"), hash("var x=1 ;\n// one\n"));
+        assert_ne!(hash("var x = 1;"), hash("var x = 2;"));
+        assert_ne!(hash("print \"a\";"), hash("print \"b\";"));
+        assert_eq!(fingerprint_of("print 1.5;"), hash("print 1.5;"));
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = Fnv1a::new();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+
+        assert_eq!(0xcbf2_9ce4_8422_2325, hash(b""));
+        assert_eq!(0xaf63_dc4c_8601_ec8c, hash(b"a"));
+        assert_eq!(0x8594_4171_f739_67e8, hash(b"foobar"));
+    }
+
+    #[test]
+    fn test_fingerprint_is_pinned() {
+        // The fingerprints are stored, e.g. as cache keys, so they must not
+        // change unless the lexemes or their hashing do.
+        assert_eq!(
+            0x69ee_f32d_ab70_eb2e,
+            fingerprint_of("var x = 1;\nprint x;")
+        );
+    }
+
+    #[test]
+    fn test_is_trivia_only() {
+        assert!(Lexer::with_source("// one\n// two\n").is_trivia_only());
+        assert!(Lexer::with_source(" \t\n\n  ").is_trivia_only());
+        assert!(Lexer::with_source("").is_trivia_only());
+        assert!(!Lexer::with_source("// one\nprint 1;\n").is_trivia_only());
+    }
+
+    #[test]
+    fn test_collect_gaps() {
+        let source = "var x = 1; // one\n\n  print  x;\n";
+        let items: Vec<(String, Token)> = Lexer::with_source(source).collect_gaps().collect();
+
+        assert_eq!("", items[0].0);
+        assert_eq!(Lexeme::Var, items[0].1.lexeme);
+        assert_eq!(" // one\n\n  ", items[5].0);
+        assert_eq!(Lexeme::Print, items[5].1.lexeme);
+        assert_eq!("\n", items[items.len() - 1].0);
+        assert_eq!(Lexeme::Eof, items[items.len() - 1].1.lexeme);
+
+        for source in &[source, "a @ b", "x = $1 #", "var s = \"abc"] {
+            let mut reconstructed = String::new();
+            for (gap, tkn) in Lexer::with_source(source).collect_gaps() {
+                reconstructed.push_str(&gap);
+                reconstructed.push_str(&reconstruct(&[tkn]));
+            }
+            assert_eq!(*source, reconstructed);
+        }
+
+        let items: Vec<(String, Token)> = Lexer::with_source("a @ b").collect_gaps().collect();
+        assert_eq!(" @ ", items[1].0);
+        assert_eq!(Lexeme::Identifier("b".to_string()), items[1].1.lexeme);
+    }
+
+    #[test]
+    fn test_strip_interior_eof() {
+        let mut tokens: Vec<Token> = Lexer::with_source("var x;").collect();
+        tokens.extend(Lexer::with_source("print x;"));
+        assert_eq!(
+            2,
+            tokens
+                .iter()
+                .filter(|tkn| tkn.lexeme == Lexeme::Eof)
+                .count()
+        );
+
+        strip_interior_eof(&mut tokens);
+
+        assert_eq!(9, tokens.len());
+        assert_eq!(
+            1,
+            tokens
+                .iter()
+                .filter(|tkn| tkn.lexeme == Lexeme::Eof)
+                .count()
+        );
+        assert_eq!(Lexeme::Eof, tokens[8].lexeme);
+        assert_eq!(Lexeme::Print, tokens[4].lexeme);
+    }
+
+    #[test]
+    fn test_significant_source() {
+        assert_eq!(
+            "var x = 1 ;",
+            Lexer::with_source("var   x=1;//c").significant_source()
+        );
+        assert_eq!(
+            "print \"a  b\" + 1.50 ;",
+            Lexer::with_source("// one\n\tprint \"a  b\"\n  + 1.50;\n").significant_source()
+        );
+        assert_eq!("", Lexer::with_source(" // c\n").significant_source());
+        assert_eq!(
+            "a @ b ;",
+            Lexer::with_source("a @\tb;").significant_source()
+        );
+        assert_eq!(
+            "print \"abc",
+            Lexer::with_source("print \"abc").significant_source()
+        );
+    }
+
+    #[test]
+    fn test_first_significant() {
+        let tkn = Lexer::first_significant(
+            "// header
+
+  class A {}",
+        )
+        .unwrap();
+        assert_eq!(Lexeme::Class, tkn.lexeme);
+        assert_eq!((3, 2), tkn.span.caret_position());
+
+        let tkn = Lexer::first_significant("var x = \"unterminated").unwrap();
+        assert_eq!(Lexeme::Var, tkn.lexeme);
+
+        assert_eq!(None, Lexer::first_significant("// only a comment\n"));
+        assert_eq!(None, Lexer::first_significant(""));
+    }
+
+    #[test]
+    fn test_with_text() {
+        let source = "var hello = \"á b\";\n// one\n  x";
+        let pairs: Vec<(Token, &str)> = Lexer::with_source(source).with_text().collect();
+
+        assert_eq!(Lexeme::Identifier("hello".to_string()), pairs[2].0.lexeme);
+        assert_eq!("hello", pairs[2].1);
+        assert_eq!("\"á b\"", pairs[6].1);
+        assert_eq!("// one\n", pairs[9].1);
+        assert_eq!("", pairs[pairs.len() - 1].1);
+        assert_eq!(
+            source,
+            pairs.iter().map(|(_, text)| *text).collect::<String>()
+        );
+    }
+
+    #[test]
+    fn test_with_text_multi_line() {
+        let source = "x\n\"a\nb\" /y";
+        let pairs: Vec<(Token, &str)> = Lexer::with_source(source).with_text().collect();
+
+        assert_eq!(Lexeme::String("a\nb".to_string()), pairs[2].0.lexeme);
+        assert_eq!("\"a\nb\"", pairs[2].1);
+
+        // The unexpected characters are skipped, so they are not in any text.
+        let pairs: Vec<(Token, &str)> = Lexer::with_source("a@b").with_text().collect();
+        assert_eq!(
+            vec!["a", "b", ""],
+            pairs.iter().map(|(_, t)| *t).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_detect_indent_unknown() {
+        let source = "print x;\nprint y;";
+        assert_eq!(IndentStyle::Unknown, indent_of(source));
+    }
+}
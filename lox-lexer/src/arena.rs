@@ -0,0 +1,156 @@
+use std::fmt::Debug;
+
+use bumpalo::Bump;
+
+use crate::lexeme::Escaped;
+use crate::lexer::Context;
+use crate::{Lexeme, Lexer, Span};
+
+/// Represents a lexeme whose text payload is allocated in an arena.
+#[derive(Clone, PartialEq)]
+pub enum ArenaLexeme<'a> {
+    /// Identity
+    Identifier(&'a str),
+    /// String
+    String(&'a str),
+    /// Number and its raw source spelling
+    Number(f64, &'a str),
+    /// Comment
+    Comment(&'a str),
+    /// Regex
+    Regex(&'a str),
+    /// Whitespace
+    Whitespace(&'a str),
+    /// Heredoc, with its tag and its body
+    Heredoc(&'a str, &'a str),
+    /// A lexeme without a text payload
+    Other(Lexeme),
+}
+
+impl<'a> Debug for ArenaLexeme<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArenaLexeme::Identifier(i) => write!(f, "IDENTITY({})", i),
+            ArenaLexeme::String(string) => write!(f, "STRING({})", Escaped(string)),
+            ArenaLexeme::Number(number, _) => write!(f, "NUMBER({})", number),
+            ArenaLexeme::Comment(comment) => write!(f, "COMMENT({})", Escaped(comment)),
+            ArenaLexeme::Regex(regex) => write!(f, "REGEX({})", regex),
+            ArenaLexeme::Whitespace(ws) => write!(f, "WHITESPACE({})", Escaped(ws)),
+            ArenaLexeme::Heredoc(tag, body) => write!(f, "HEREDOC({}:{})", tag, Escaped(body)),
+            ArenaLexeme::Other(l) => write!(f, "{:?}", l),
+        }
+    }
+}
+
+/// Represents a token whose text payload is allocated in an arena.
+pub struct ArenaToken<'a> {
+    lexeme: ArenaLexeme<'a>,
+    span: Span,
+}
+
+impl<'a> ArenaToken<'a> {
+    /// Returns the lexeme of the token.
+    #[inline]
+    pub fn lexeme(&self) -> &ArenaLexeme<'a> {
+        &self.lexeme
+    }
+
+    /// Returns the span of the token.
+    #[inline]
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl<'a> Debug for ArenaToken<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} [{:?}]", self.lexeme, self.span)
+    }
+}
+
+/// An iterator for the tokens of a source string, which allocates
+/// the text payloads in an arena. See [Lexer::with_source_in].
+pub struct ArenaLexerIter<'a> {
+    ctx: Context<'a>,
+    arena: &'a Bump,
+}
+
+impl<'a> Iterator for ArenaLexerIter<'a> {
+    type Item = ArenaToken<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tkn = self.ctx.read_token()?;
+
+        let lexeme = match tkn.lexeme {
+            Lexeme::Identifier(_) => ArenaLexeme::Identifier(self.alloc_payload()),
+            Lexeme::String(_) => ArenaLexeme::String(self.alloc_payload()),
+            Lexeme::Number(number) => ArenaLexeme::Number(number.value, self.alloc_payload()),
+            Lexeme::Comment { .. } => ArenaLexeme::Comment(self.alloc_payload()),
+            Lexeme::Regex(_) => ArenaLexeme::Regex(self.alloc_payload()),
+            Lexeme::Whitespace(_) => ArenaLexeme::Whitespace(self.alloc_payload()),
+            Lexeme::Heredoc { tag, .. } => {
+                ArenaLexeme::Heredoc(self.arena.alloc_str(&tag), self.alloc_payload())
+            }
+            l => ArenaLexeme::Other(l),
+        };
+
+        Some(ArenaToken {
+            lexeme,
+            span: tkn.span,
+        })
+    }
+}
+
+impl<'a> ArenaLexerIter<'a> {
+    /// Copies the payload of the last read token into the arena.
+    fn alloc_payload(&self) -> &'a str {
+        self.arena.alloc_str(self.ctx.payload())
+    }
+}
+
+impl Lexer {
+    /// Returns an iterator which containts the tokens resulted from parsing
+    /// the source string. The text payloads of the tokens (identifiers,
+    /// strings, numbers, comments and whitespaces) are allocated in the arena,
+    /// so they are all freed at once when the arena is dropped or reset.
+    pub fn with_source_in<'a>(source: &'a str, arena: &'a Bump) -> ArenaLexerIter<'a> {
+        let mut ctx = Context::new(source);
+        ctx.options.buffered_payloads = true;
+
+        ArenaLexerIter { ctx, arena }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_source_in() {
+        let arena = Bump::new();
+        let source = "var x = \"lox\"; // 12.5";
+        let tkns: Vec<ArenaToken> = Lexer::with_source_in(source, &arena).collect();
+
+        assert_eq!(ArenaLexeme::Other(Lexeme::Var), tkns[0].lexeme);
+        assert_eq!(ArenaLexeme::Whitespace(" "), tkns[1].lexeme);
+        assert_eq!(ArenaLexeme::Identifier("x"), tkns[2].lexeme);
+        assert_eq!(ArenaLexeme::String("lox"), tkns[6].lexeme);
+        assert_eq!(ArenaLexeme::Comment(" 12.5"), tkns[9].lexeme);
+        assert_eq!(ArenaLexeme::Other(Lexeme::Eof), tkns[10].lexeme);
+    }
+
+    #[test]
+    fn test_with_source_in_spans() {
+        let arena = Bump::new();
+        let source = "print 12.5;";
+
+        let expected: Vec<Span> = Lexer::with_source(source).map(|t| t.span).collect();
+        let actual: Vec<Span> = Lexer::with_source_in(source, &arena)
+            .map(|t| t.span())
+            .collect();
+        assert!(expected == actual);
+
+        let tkns: Vec<ArenaToken> = Lexer::with_source_in(source, &arena).collect();
+        assert_eq!(&ArenaLexeme::Number(12.5, "12.5"), tkns[2].lexeme());
+    }
+}
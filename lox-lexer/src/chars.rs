@@ -21,16 +21,22 @@ pub(crate) const CHAR_CARRIAGE_RETURN: char = '\r';
 pub(crate) const CHAR_TAB: char = '\t';
 
 pub(crate) const CHAR_DOUBLE_QUOTE: char = '"';
+pub(crate) const CHAR_BACKSLASH: char = '\\';
 
 pub(crate) const CHAR_0: char = '0';
 pub(crate) const CHAR_9: char = '9';
 
 pub(crate) const CHAR_LOWERCASE_A: char = 'a';
+pub(crate) const CHAR_LOWERCASE_E: char = 'e';
 pub(crate) const CHAR_LOWERCASE_Z: char = 'z';
 pub(crate) const CHAR_UPPERCASE_A: char = 'A';
+pub(crate) const CHAR_UPPERCASE_E: char = 'E';
 pub(crate) const CHAR_UPPERCASE_Z: char = 'Z';
 pub(crate) const CHAR_UNDERSCORE: char = '_';
 
+pub(crate) const CHAR_RAW_PREFIX: char = 'r';
+pub(crate) const CHAR_HASH: char = '#';
+
 #[inline]
 pub(crate) fn is_whitespace(c: char) -> bool {
     c == CHAR_WHITESPACE || c == CHAR_TAB || c == CHAR_CARRIAGE_RETURN
@@ -0,0 +1,88 @@
+use crate::chars::*;
+use crate::LexerIter;
+
+/// Classifies the characters of the identifiers and of the whitespaces,
+/// e.g. to accept unicode identifiers or a "$" in the identifiers. The
+/// provided methods follow the default rules of the lexer, so a classifier
+/// overrides only the rules it changes.
+pub trait CharClassifier {
+    /// Checks if a character starts an identifier.
+    fn is_ident_start(&self, c: char) -> bool {
+        is_alpha(c)
+    }
+
+    /// Checks if a character continues an identifier.
+    fn is_ident_continue(&self, c: char) -> bool {
+        is_alphanum(c)
+    }
+
+    /// Checks if a character is a whitespace, other than the new line.
+    fn is_whitespace(&self, c: char) -> bool {
+        is_whitespace(c)
+    }
+}
+
+impl<'a> LexerIter<'a> {
+    /// Classifies the characters of the identifiers and of the whitespaces
+    /// with a custom classifier, instead of the default rules. A character
+    /// which starts an identifier is read as an identifier even when it
+    /// would start another token, e.g. a digit.
+    pub fn classifier(mut self, classifier: Box<dyn CharClassifier + 'a>) -> Self {
+        self.ctx.hooks.classifier = Some(classifier);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Lexeme, Lexer, Token};
+
+    struct DigitIdentifiers;
+
+    impl CharClassifier for DigitIdentifiers {
+        fn is_ident_start(&self, c: char) -> bool {
+            is_alphanum(c)
+        }
+    }
+
+    struct DollarIdentifiers;
+
+    impl CharClassifier for DollarIdentifiers {
+        fn is_ident_start(&self, c: char) -> bool {
+            c == '$' || is_alpha(c)
+        }
+
+        fn is_ident_continue(&self, c: char) -> bool {
+            c == '$' || is_alphanum(c)
+        }
+
+        fn is_whitespace(&self, c: char) -> bool {
+            c == '\u{a0}' || is_whitespace(c)
+        }
+    }
+
+    #[test]
+    fn test_classifier_digit_starts() {
+        let tkns: Vec<Token> = Lexer::with_source("var 2d = 3x;")
+            .classifier(Box::new(DigitIdentifiers))
+            .collect();
+
+        assert_eq!(Lexeme::Var, tkns[0].lexeme);
+        assert_eq!(Lexeme::Identifier("2d".to_string()), tkns[2].lexeme);
+        assert_eq!(Lexeme::Identifier("3x".to_string()), tkns[6].lexeme);
+        assert_eq!(Lexeme::Semicolon, tkns[7].lexeme);
+    }
+
+    #[test]
+    fn test_classifier_dollar_and_whitespace() {
+        let tkns: Vec<Token> = Lexer::with_source("$a\u{a0}b$")
+            .classifier(Box::new(DollarIdentifiers))
+            .collect();
+
+        assert_eq!(Lexeme::Identifier("$a".to_string()), tkns[0].lexeme);
+        assert_eq!(Lexeme::Whitespace("\u{a0}".to_string()), tkns[1].lexeme);
+        assert_eq!(Lexeme::Identifier("b$".to_string()), tkns[2].lexeme);
+        assert_eq!(Lexeme::Eof, tkns[3].lexeme);
+    }
+}
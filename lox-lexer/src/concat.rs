@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+
+use crate::analysis::is_trivia;
+use crate::{Lexeme, LexerIter, Token};
+
+/// An iterator which merges the adjacent 'string' tokens, separated only
+/// by trivia, into one 'string' token, as for the implicit concatenation
+/// of the string literals. The trivia between the merged strings is dropped.
+pub struct ConcatAdjacentStrings<'a> {
+    lxr: LexerIter<'a>,       // the tokens to merge
+    pending: VecDeque<Token>, // the tokens read ahead, after a string
+}
+
+impl<'a> Iterator for ConcatAdjacentStrings<'a> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(tkn) = self.pending.pop_front() {
+            return Some(tkn);
+        }
+
+        let mut tkn = self.lxr.next()?;
+        if !matches!(tkn.lexeme, Lexeme::String(_)) {
+            return Some(tkn);
+        }
+
+        // Read ahead over the trivia, looking for the next string.
+        for next in self.lxr.by_ref() {
+            if is_trivia(&next.lexeme) && next.lexeme != Lexeme::Eof {
+                self.pending.push_back(next);
+                continue;
+            }
+
+            if let (Lexeme::String(s), Lexeme::String(next_s)) = (&mut tkn.lexeme, &next.lexeme) {
+                s.push_str(next_s);
+                tkn.span.end_line = next.span.end_line;
+                tkn.span.end_col = next.span.end_col;
+                self.pending.clear();
+            } else {
+                self.pending.push_back(next);
+                break;
+            }
+        }
+
+        Some(tkn)
+    }
+}
+
+impl<'a> LexerIter<'a> {
+    /// Returns an iterator which merges the 'string' tokens separated only by
+    /// trivia into one token, e.g. `"a" "b"` into `"ab"`. The span of the
+    /// merged token covers all the strings. The other tokens pass through.
+    pub fn concat_adjacent_strings(self) -> ConcatAdjacentStrings<'a> {
+        ConcatAdjacentStrings {
+            lxr: self,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lexer;
+
+    #[test]
+    fn test_concat_adjacent_strings() {
+        let tkns: Vec<Token> = Lexer::with_source("x = \"a\" \"b\"\n  \"c\";")
+            .concat_adjacent_strings()
+            .collect();
+
+        assert_eq!(Lexeme::String("abc".to_string()), tkns[4].lexeme);
+        assert_eq!(
+            (1, 4),
+            (tkns[4].span.start_line.0, tkns[4].span.start_col.0)
+        );
+        assert_eq!((2, 5), (tkns[4].span.end_line.0, tkns[4].span.end_col.0));
+        assert_eq!(Lexeme::Semicolon, tkns[5].lexeme);
+        assert_eq!(7, tkns.len());
+    }
+
+    #[test]
+    fn test_concat_adjacent_strings_with_operator() {
+        let tkns: Vec<Token> = Lexer::with_source("\"a\" + \"b\"")
+            .concat_adjacent_strings()
+            .collect();
+        let expected: Vec<Token> = Lexer::with_source("\"a\" + \"b\"").collect();
+
+        assert_eq!(expected, tkns);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::chars::*;
+use crate::{LexError, Span};
+
+impl LexError {
+    /// Renders the error with the line of the source where it was reported
+    /// and a caret under its span, in the style of a compiler diagnostic.
+    /// A span over multiple lines is underlined until the end of its first line.
+    pub fn render(&self, source: &str) -> String {
+        let span = self.span();
+        let line = span.start_line.0;
+        let text = source.lines().nth(line.wrapping_sub(1)).unwrap_or("");
+
+        let gutter = " ".repeat(line.to_string().len());
+
+        format!(
+            "error: {}\n{}--> {}:{}\n{} |\n{} | {}\n{} | {}\n",
+            self.message(),
+            gutter,
+            line,
+            span.start_col.0,
+            gutter,
+            line,
+            text,
+            gutter,
+            underline(text, span)
+        )
+    }
+}
+
+/// Returns the carets which underline a span on the text of its first line.
+/// A span over multiple lines is underlined until the end of the line.
+fn underline(text: &str, span: Span) -> String {
+    // Keep the tabs, so the caret is aligned with the text.
+    let indent: String = text
+        .chars()
+        .take(span.start_col.0)
+        .map(|c| {
+            if c == CHAR_TAB {
+                CHAR_TAB
+            } else {
+                CHAR_WHITESPACE
+            }
+        })
+        .collect();
+
+    let line_len = text.chars().count();
+    let end = if span.is_one_line() {
+        span.end_col.0.min(line_len)
+    } else {
+        line_len
+    };
+    let carets = "^".repeat(end.saturating_sub(span.start_col.0).max(1));
+
+    indent + &carets
+}
+
+/// Renders a message about a span with the lines of the source around it:
+/// up to `context_lines` lines before and after the first line of the span,
+/// with their numbers in a gutter, and the span underlined with carets.
+/// The context is clamped at the start and at the end of the source.
+pub fn render_with_context(
+    source: &str,
+    span: Span,
+    context_lines: usize,
+    message: &str,
+) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let line = span.start_line.0;
+
+    let first = line.saturating_sub(context_lines).max(1);
+    let last = (line + context_lines).min(lines.len().max(line));
+    let width = last.to_string().len();
+    let gutter = " ".repeat(width);
+
+    let mut rendered = format!(
+        "error: {}\n{}--> {}:{}\n{} |\n",
+        message, gutter, line, span.start_col.0, gutter
+    );
+
+    for n in first..=last {
+        let text = lines.get(n - 1).copied().unwrap_or("");
+        rendered.push_str(&format!("{:>width$} | {}\n", n, text, width = width));
+
+        if n == line {
+            rendered.push_str(&format!("{} | {}\n", gutter, underline(text, span)));
+        }
+    }
+
+    rendered
+}
+
+/// Renders a batch of errors as a compiler-style report: a summary count,
+/// followed by each error in source order, with its line and a caret.
+pub fn report_errors(source: &str, errors: &[LexError]) -> String {
+    let mut sorted: Vec<&LexError> = errors.iter().collect();
+    sorted.sort_by_key(|e| e.span());
+
+    let mut report = match errors.len() {
+        1 => "1 error\n".to_string(),
+        n => format!("{} errors\n", n),
+    };
+
+    for e in sorted {
+        report.push('\n');
+        report.push_str(&e.render(source));
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Column, Lexer, Line};
+
+    #[test]
+    fn test_render() {
+        let source = "var x;\n123abc;";
+        let output = Lexer::with_source(source).strict_numbers().lex_all();
+
+        let expected = "\
+error: number is immediately followed by an identifier
+ --> 2:0
+  |
+2 | 123abc;
+  | ^^^^^^
+";
+        assert_eq!(expected, output.errors[0].render(source));
+    }
+
+    #[test]
+    fn test_render_keeps_tabs() {
+        let source = "\t \tx;";
+        let output = Lexer::with_source(source)
+            .lint_mixed_indentation()
+            .lex_all();
+
+        let rendered = output.errors[0].render(source);
+        assert!(rendered.ends_with("1 | \t \tx;\n  | ^^^\n"));
+    }
+
+    #[test]
+    fn test_report_errors() {
+        let source = "x = /a;\nprint 1;\n12ab;";
+        let output = Lexer::with_source(source)
+            .regex_literals()
+            .strict_numbers()
+            .lex_all();
+        let mut errors = output.errors;
+        errors.reverse();
+
+        let report = report_errors(source, &errors);
+
+        assert!(report.starts_with("2 errors\n"));
+        let first = report.find(" --> 1:4").unwrap();
+        let second = report.find(" --> 3:0").unwrap();
+        assert!(first < second);
+        assert!(report.contains("1 | x = /a;"));
+        assert!(report.contains("3 | 12ab;"));
+    }
+
+    #[test]
+    fn test_render_with_context() {
+        let source = "var a;\nvar b;\nprint a +;\nvar c;\nvar d;\nvar e;";
+        let span = Span::new(Line(3), Column(9));
+
+        let expected = "\
+error: expected an expression
+ --> 3:9
+  |
+1 | var a;
+2 | var b;
+3 | print a +;
+  |          ^
+4 | var c;
+5 | var d;
+";
+        assert_eq!(
+            expected,
+            render_with_context(source, span, 2, "expected an expression")
+        );
+    }
+
+    #[test]
+    fn test_render_with_context_clamped() {
+        let source = "var a;\nprint a +;";
+        let span = Span::new(Line(2), Column(9));
+
+        let expected = "\
+error: expected an expression
+ --> 2:9
+  |
+1 | var a;
+2 | print a +;
+  |          ^
+";
+        assert_eq!(
+            expected,
+            render_with_context(source, span, 2, "expected an expression")
+        );
+    }
+}
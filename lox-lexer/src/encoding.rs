@@ -0,0 +1,24 @@
+/// How the columns of the spans are counted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PositionEncoding {
+    /// A character advances the column by its length in UTF-8 bytes
+    Utf8Bytes,
+    /// A character advances the column by its length in UTF-16 code
+    /// units, 1 or 2, as the LSP positions
+    Utf16,
+    /// A character advances the column by 1
+    #[default]
+    ScalarValues,
+}
+
+impl PositionEncoding {
+    /// Returns the number of columns a character advances.
+    #[inline]
+    pub(crate) fn width(self, c: char) -> usize {
+        match self {
+            PositionEncoding::Utf8Bytes => c.len_utf8(),
+            PositionEncoding::Utf16 => c.len_utf16(),
+            PositionEncoding::ScalarValues => 1,
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::Span;
+
+/// Represents an error reported by the lexer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LexError {
+    /// A leading whitespace mixes tabs and spaces
+    MixedIndentation {
+        /// The span of the leading whitespace
+        span: Span,
+    },
+    /// A character which does not start any token
+    UnexpectedChar {
+        /// The character
+        c: char,
+        /// The span of the character
+        span: Span,
+    },
+    /// A string is not terminated by its closing delimiter
+    UnterminatedString {
+        /// The span from the opening delimiter to the end of the source
+        span: Span,
+    },
+    /// A regex literal is not terminated by its closing `/` on its line
+    UnterminatedRegex {
+        /// The span from the opening `/` to the end of the line or of the source
+        span: Span,
+    },
+    /// A heredoc is not closed by a line with its tag
+    UnterminatedHeredoc {
+        /// The tag of the heredoc
+        tag: String,
+        /// The span from the opening `<<` to the end of the source
+        span: Span,
+    },
+    /// A `*/` closes a block comment which was never opened
+    UnexpectedCommentClose {
+        /// The span of the `*/`
+        span: Span,
+    },
+    /// A number is immediately followed by an identifier, e.g. `123abc`
+    MalformedNumber {
+        /// The span of the number and of the identifier
+        span: Span,
+    },
+    /// A span is outside of the source
+    InvalidSpan {
+        /// The span
+        span: Span,
+    },
+    /// An integer literal does not fit in an `i64`, see [crate::OverflowMode::Error]
+    IntegerOverflow {
+        /// The span of the literal
+        span: Span,
+    },
+    /// A line is longer than the configured limit
+    LineTooLong {
+        /// The line, starting from 1
+        line: usize,
+        /// The maximum number of characters of a line
+        limit: usize,
+        /// The span of the first character past the limit
+        span: Span,
+    },
+}
+
+impl LexError {
+    /// Returns the span where the error was reported.
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::MixedIndentation { span }
+            | LexError::UnexpectedChar { span, .. }
+            | LexError::UnterminatedString { span }
+            | LexError::UnterminatedRegex { span }
+            | LexError::UnterminatedHeredoc { span, .. }
+            | LexError::UnexpectedCommentClose { span }
+            | LexError::MalformedNumber { span }
+            | LexError::InvalidSpan { span }
+            | LexError::IntegerOverflow { span }
+            | LexError::LineTooLong { span, .. } => *span,
+        }
+    }
+
+    /// Checks if the error is reported by a lint about the layout of the
+    /// source, e.g. a long line, and not by a malformed token.
+    pub(crate) fn is_lint(&self) -> bool {
+        matches!(
+            self,
+            LexError::MixedIndentation { .. } | LexError::LineTooLong { .. }
+        )
+    }
+
+    /// Returns a short description of the error.
+    pub fn message(&self) -> String {
+        match self {
+            LexError::MixedIndentation { .. } => "indentation mixes tabs and spaces".to_string(),
+            LexError::UnexpectedChar { c, .. } => format!("unexpected character `{}`", c),
+            LexError::UnterminatedString { .. } => "unterminated string".to_string(),
+            LexError::UnterminatedRegex { .. } => "unterminated regex".to_string(),
+            LexError::UnterminatedHeredoc { tag, .. } => format!("unterminated heredoc `{}`", tag),
+            LexError::UnexpectedCommentClose { .. } => {
+                "`*/` outside of a block comment".to_string()
+            }
+            LexError::MalformedNumber { .. } => {
+                "number is immediately followed by an identifier".to_string()
+            }
+            LexError::InvalidSpan { .. } => "span is outside of the source".to_string(),
+            LexError::IntegerOverflow { .. } => {
+                "integer literal does not fit in 64 bits".to_string()
+            }
+            LexError::LineTooLong { limit, .. } => {
+                format!("line is longer than {} characters", limit)
+            }
+        }
+    }
+}
+
+/// Formats the message of the error with the start of its span,
+/// e.g. `unterminated string at line 3, column 5`. As in the spans,
+/// the lines start at 1 and the columns at 0.
+impl Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let span = self.span();
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message(),
+            span.start_line.0,
+            span.start_col.0
+        )
+    }
+}
+
+impl Error for LexError {}
+
+/// Represents a non-fatal warning reported by the lexer.
+/// The token is still produced normally.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LexWarning {
+    /// A decimal number literal starts with a leading zero, e.g. `0123`
+    LeadingZero {
+        /// The span of the number
+        span: Span,
+    },
+    /// An integer literal does not fit in an `i64` and it may lose
+    /// precision as a float, see [crate::OverflowMode::ToFloat]
+    PrecisionLoss {
+        /// The span of the literal
+        span: Span,
+    },
+}
+
+impl LexWarning {
+    /// Returns the span where the warning was reported.
+    pub fn span(&self) -> Span {
+        match self {
+            LexWarning::LeadingZero { span } | LexWarning::PrecisionLoss { span } => *span,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Column, Line};
+
+    #[test]
+    fn test_display() {
+        let span = Span::new(Line(3), Column(5));
+        let errors = vec![
+            (
+                LexError::MixedIndentation { span },
+                "indentation mixes tabs and spaces at line 3, column 5",
+            ),
+            (
+                LexError::UnexpectedChar { c: '@', span },
+                "unexpected character `@` at line 3, column 5",
+            ),
+            (
+                LexError::UnterminatedString { span },
+                "unterminated string at line 3, column 5",
+            ),
+            (
+                LexError::UnterminatedRegex { span },
+                "unterminated regex at line 3, column 5",
+            ),
+            (
+                LexError::UnterminatedHeredoc {
+                    tag: "END".to_string(),
+                    span,
+                },
+                "unterminated heredoc `END` at line 3, column 5",
+            ),
+            (
+                LexError::UnexpectedCommentClose { span },
+                "`*/` outside of a block comment at line 3, column 5",
+            ),
+            (
+                LexError::MalformedNumber { span },
+                "number is immediately followed by an identifier at line 3, column 5",
+            ),
+            (
+                LexError::InvalidSpan { span },
+                "span is outside of the source at line 3, column 5",
+            ),
+            (
+                LexError::IntegerOverflow { span },
+                "integer literal does not fit in 64 bits at line 3, column 5",
+            ),
+            (
+                LexError::LineTooLong {
+                    line: 3,
+                    limit: 80,
+                    span,
+                },
+                "line is longer than 80 characters at line 3, column 5",
+            ),
+        ];
+
+        for (error, expected) in errors {
+            assert_eq!(expected, error.to_string());
+        }
+    }
+
+    #[test]
+    fn test_error() {
+        fn check(source: &str) -> Result<(), Box<dyn Error>> {
+            let output = crate::Lexer::with_source(source).strict_numbers().lex_all();
+            match output.errors.into_iter().next() {
+                Some(error) => Err(error)?,
+                None => Ok(()),
+            }
+        }
+
+        assert!(check("var x;").is_ok());
+        assert_eq!(
+            "number is immediately followed by an identifier at line 1, column 4",
+            check("var 1x;").unwrap_err().to_string()
+        );
+    }
+}
@@ -0,0 +1,10 @@
+/// How the lexer handles the escape sequences in the string literals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum EscapePolicy {
+    /// The string keeps the escapes verbatim, as they are in the source
+    #[default]
+    Raw,
+    /// The escapes `\n`, `\t`, `\r`, `\0`, `\\`, `\"` and `\u{...}` are
+    /// decoded. Any other escape is kept verbatim.
+    Decoded,
+}
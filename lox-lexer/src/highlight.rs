@@ -0,0 +1,179 @@
+use crate::chars::*;
+use crate::{Lexeme, Token};
+
+/// The syntax highlighting group of a lexeme.
+///
+/// The discriminant of each group is its index in [HighlightGroup::LEGEND].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HighlightGroup {
+    /// Keywords
+    Keyword = 0,
+    /// Identifiers
+    Variable = 1,
+    /// String literals
+    String = 2,
+    /// Number literals
+    Number = 3,
+    /// Comments
+    Comment = 4,
+    /// Operators
+    Operator = 5,
+}
+
+impl HighlightGroup {
+    /// The LSP semantic token types, in the order of the groups.
+    pub const LEGEND: [&'static str; 6] = [
+        "keyword", "variable", "string", "number", "comment", "operator",
+    ];
+}
+
+impl Lexeme {
+    /// Returns the highlighting group of the lexeme, or `None`
+    /// for punctuation, whitespaces, new lines and the eof.
+    pub fn highlight_group(&self) -> Option<HighlightGroup> {
+        use Lexeme::*;
+
+        match self {
+            Minus | Plus | Slash | Star | Bang | BangEqual | Equal | EqualEqual | Greater
+            | GreaterEqual | Less | LessEqual => Some(HighlightGroup::Operator),
+            Identifier(_) | Symbol(_) => Some(HighlightGroup::Variable),
+            String(_) | Regex(_) | Heredoc { .. } => Some(HighlightGroup::String),
+            Number(_) => Some(HighlightGroup::Number),
+            Comment { .. } => Some(HighlightGroup::Comment),
+            l if l.canonical_keyword_text().is_some() => Some(HighlightGroup::Keyword),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the length of the token on its first line, in UTF-16 code units.
+fn first_line_len(token: &Token) -> usize {
+    let span = token.span;
+    if span.is_one_line() {
+        return span.end_col.0 - span.start_col.0;
+    }
+
+    // Only strings and comments can span multiple lines.
+    let (prefix, text) = match &token.lexeme {
+        Lexeme::String(s) => (1, s.as_str()),
+        Lexeme::Comment { text, style } => (style.delimiters().0.len(), text.as_str()),
+        Lexeme::Heredoc { tag, .. } => (2 + tag.encode_utf16().count(), ""),
+        _ => (0, ""),
+    };
+
+    let first_line = text.chars().take_while(|c| *c != CHAR_NEWLINE);
+    prefix + first_line.map(char::len_utf16).sum::<usize>()
+}
+
+/// Encodes the tokens as LSP semantic tokens: five integers per highlighted
+/// token, `[deltaLine, deltaStart, length, tokenType, tokenModifiers]`,
+/// where the deltas are relative to the previous highlighted token and the
+/// token type is the index of its [HighlightGroup] in [HighlightGroup::LEGEND].
+///
+/// Multi-line tokens are reported on their first line only. The LSP
+/// counts the columns and the lengths in UTF-16 code units, so the tokens
+/// must be lexed with [PositionEncoding::Utf16], which gives such columns.
+/// With the default encoding, the positions are off after any character
+/// outside of the Basic Multilingual Plane, e.g. an emoji.
+///
+/// [PositionEncoding::Utf16]: crate::PositionEncoding::Utf16
+pub fn lsp_semantic_tokens(tokens: &[Token]) -> Vec<u32> {
+    let mut data = Vec::new();
+    let mut prev_line = 1;
+    let mut prev_col = 0;
+
+    for token in tokens {
+        if let Some(group) = token.lexeme.highlight_group() {
+            let line = token.span.start_line.0;
+            let col = token.span.start_col.0;
+
+            let delta_line = line - prev_line;
+            let delta_start = if delta_line == 0 { col - prev_col } else { col };
+
+            data.push(delta_line as u32);
+            data.push(delta_start as u32);
+            data.push(first_line_len(token) as u32);
+            data.push(group as u32);
+            data.push(0);
+
+            prev_line = line;
+            prev_col = col;
+        }
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Lexer, PositionEncoding};
+
+    #[test]
+    fn test_highlight_group() {
+        assert_eq!(
+            Some(HighlightGroup::Keyword),
+            Lexeme::While.highlight_group()
+        );
+        assert_eq!(
+            Some(HighlightGroup::Operator),
+            Lexeme::EqualEqual.highlight_group()
+        );
+        assert_eq!(
+            Some(HighlightGroup::Variable),
+            Lexeme::Identifier("x".to_string()).highlight_group()
+        );
+        assert_eq!(None, Lexeme::Semicolon.highlight_group());
+        assert_eq!(None, Lexeme::NewLine.highlight_group());
+        assert_eq!(
+            "keyword",
+            HighlightGroup::LEGEND[HighlightGroup::Keyword as usize]
+        );
+    }
+
+    #[test]
+    fn test_lsp_semantic_tokens() {
+        let tokens: Vec<Token> = Lexer::with_source("var x = 1;\n  // hi").collect();
+        let data = lsp_semantic_tokens(&tokens);
+
+        #[rustfmt::skip]
+        let expected = vec![
+            0, 0, 3, 0, 0, // var
+            0, 4, 1, 1, 0, // x
+            0, 2, 1, 5, 0, // =
+            0, 2, 1, 3, 0, // 1
+            1, 2, 5, 4, 0, // // hi
+        ];
+        assert_eq!(expected, data);
+    }
+
+    #[test]
+    fn test_lsp_semantic_tokens_multi_line() {
+        let tokens: Vec<Token> = Lexer::with_source("// one\n\"a\nbc\" x").collect();
+        let data = lsp_semantic_tokens(&tokens);
+
+        #[rustfmt::skip]
+        let expected = vec![
+            0, 0, 6, 4, 0, // // one
+            1, 0, 2, 2, 0, // "a
+            1, 4, 1, 1, 0, // x
+        ];
+        assert_eq!(expected, data);
+    }
+
+    #[test]
+    fn test_lsp_semantic_tokens_utf16() {
+        let tokens: Vec<Token> = Lexer::with_source("\"\u{1F600}\" x\n\"\u{1F600}\nb\"")
+            .position_encoding(PositionEncoding::Utf16)
+            .collect();
+        let data = lsp_semantic_tokens(&tokens);
+
+        #[rustfmt::skip]
+        let expected = vec![
+            0, 0, 4, 2, 0, // "😀"
+            0, 5, 1, 1, 0, // x
+            1, 0, 3, 2, 0, // "😀
+        ];
+        assert_eq!(expected, data);
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use crate::LexerIter;
+
+/// The interned symbol of an identifier.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(pub u32);
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Interns the names of the identifiers, so the repeated identifiers
+/// are stored once and are represented by the same [Symbol].
+pub trait Interner {
+    /// Returns the symbol of a name, the same one for the same name.
+    fn intern(&mut self, s: &str) -> Symbol;
+}
+
+/// A simple interner, which keeps the names in the order they were
+/// interned, so a symbol is the index of its name.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    symbols: HashMap<String, Symbol>, // the symbols of the names
+    names: Vec<String>,               // the names, indexed by their symbols
+}
+
+impl SymbolTable {
+    /// Creates an empty symbol table.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the name of a symbol, or `None` for an unknown symbol.
+    #[inline]
+    pub fn resolve(&self, symbol: Symbol) -> Option<&str> {
+        self.names.get(symbol.0 as usize).map(String::as_str)
+    }
+
+    /// Returns the number of interned names.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns true if no name was interned.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl<I: Interner + ?Sized> Interner for &mut I {
+    #[inline]
+    fn intern(&mut self, s: &str) -> Symbol {
+        (**self).intern(s)
+    }
+}
+
+impl Interner for SymbolTable {
+    fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(s) {
+            return *symbol;
+        }
+
+        let symbol = Symbol(self.names.len() as u32);
+        self.symbols.insert(s.to_string(), symbol);
+        self.names.push(s.to_string());
+        symbol
+    }
+}
+
+impl<'a> LexerIter<'a> {
+    /// Interns the names of the identifiers, which are then returned as
+    /// 'symbol' lexemes instead of 'identifier' lexemes, so the repeated
+    /// identifiers do not allocate a string for each occurrence. The interner
+    /// can be borrowed, e.g. `&mut table`, or owned by the lexer.
+    pub fn intern_with(mut self, interner: impl Interner + 'a) -> Self {
+        self.ctx.hooks.interner = Some(Box::new(interner));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Lexeme, Lexer, Token};
+
+    #[test]
+    fn test_intern_with() {
+        let mut table = SymbolTable::new();
+        let tkns: Vec<Token> = Lexer::with_source("var abc = abc + r#xyz;")
+            .intern_with(&mut table)
+            .collect();
+
+        assert_eq!(Lexeme::Var, tkns[0].lexeme);
+        assert_eq!(Lexeme::Symbol(Symbol(0)), tkns[2].lexeme);
+        assert_eq!(Lexeme::Symbol(Symbol(0)), tkns[6].lexeme);
+        assert_eq!(Lexeme::Symbol(Symbol(1)), tkns[10].lexeme);
+
+        assert_eq!(2, table.len());
+        assert_eq!(Some("abc"), table.resolve(Symbol(0)));
+        assert_eq!(Some("xyz"), table.resolve(Symbol(1)));
+        assert_eq!(None, table.resolve(Symbol(2)));
+    }
+
+    #[test]
+    fn test_intern_with_owned_lexer() {
+        let mut table = SymbolTable::new();
+        let source = "var abc = abc;".to_string();
+        let tkns: Vec<Token> = Lexer::into_iter_owned(source)
+            .into_iter()
+            .intern_with(&mut table)
+            .collect();
+
+        assert_eq!(Lexeme::Symbol(Symbol(0)), tkns[2].lexeme);
+        assert_eq!(Lexeme::Symbol(Symbol(0)), tkns[6].lexeme);
+        assert_eq!(Some("abc"), table.resolve(Symbol(0)));
+    }
+
+    #[test]
+    fn test_intern() {
+        let mut table = SymbolTable::new();
+        let a = table.intern("a");
+        let b = table.intern("b");
+
+        assert_ne!(a, b);
+        assert_eq!(a, table.intern("a"));
+        assert_eq!(2, table.len());
+    }
+}
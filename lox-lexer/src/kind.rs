@@ -0,0 +1,420 @@
+use std::fmt;
+use std::iter::FromIterator;
+
+use crate::Lexeme;
+
+/// Represents the kind of a lexeme, without its payload.
+/// The kinds are ordered as they are declared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LexemeKind {
+    /// Left parenthesis
+    LeftParen,
+    /// Right parenthesis
+    RightParen,
+    /// Left brace
+    LeftBrace,
+    /// Right brace
+    RightBrace,
+    /// Comma
+    Comma,
+    /// Dot
+    Dot,
+    /// Minus
+    Minus,
+    /// Plus
+    Plus,
+    /// Semicolon
+    Semicolon,
+    /// Slash
+    Slash,
+    /// Star
+    Star,
+    /// Bang
+    Bang,
+    /// BangEqual
+    BangEqual,
+    /// Equal
+    Equal,
+    /// EqualEqual
+    EqualEqual,
+    /// Greater
+    Greater,
+    /// GreaterEqual
+    GreaterEqual,
+    /// Less
+    Less,
+    /// LessEqual
+    LessEqual,
+    /// DotDot
+    DotDot,
+    /// Ellipsis
+    Ellipsis,
+    /// Identity
+    Identifier,
+    /// String
+    String,
+    /// Number
+    Number,
+    /// Comment
+    Comment,
+    /// Regex
+    Regex,
+    /// Heredoc
+    Heredoc,
+    /// And
+    And,
+    /// Class
+    Class,
+    /// Else
+    Else,
+    /// False
+    False,
+    /// Fun
+    Fun,
+    /// For
+    For,
+    /// If
+    If,
+    /// Nil
+    Nil,
+    /// Or
+    Or,
+    /// Print
+    Print,
+    /// Return
+    Return,
+    /// Super
+    Super,
+    /// This
+    This,
+    /// True
+    True,
+    /// Var
+    Var,
+    /// While
+    While,
+    /// Whitespace
+    Whitespace,
+    /// New line
+    NewLine,
+    /// BOF
+    Bof,
+    /// EOF
+    Eof,
+}
+
+impl Lexeme {
+    /// Returns the kind of the lexeme.
+    pub fn kind(&self) -> LexemeKind {
+        match self {
+            Lexeme::LeftParen => LexemeKind::LeftParen,
+            Lexeme::RightParen => LexemeKind::RightParen,
+            Lexeme::LeftBrace => LexemeKind::LeftBrace,
+            Lexeme::RightBrace => LexemeKind::RightBrace,
+            Lexeme::Comma => LexemeKind::Comma,
+            Lexeme::Dot => LexemeKind::Dot,
+            Lexeme::Minus => LexemeKind::Minus,
+            Lexeme::Plus => LexemeKind::Plus,
+            Lexeme::Semicolon => LexemeKind::Semicolon,
+            Lexeme::Slash => LexemeKind::Slash,
+            Lexeme::Star => LexemeKind::Star,
+            Lexeme::Bang => LexemeKind::Bang,
+            Lexeme::BangEqual => LexemeKind::BangEqual,
+            Lexeme::Equal => LexemeKind::Equal,
+            Lexeme::EqualEqual => LexemeKind::EqualEqual,
+            Lexeme::Greater => LexemeKind::Greater,
+            Lexeme::GreaterEqual => LexemeKind::GreaterEqual,
+            Lexeme::Less => LexemeKind::Less,
+            Lexeme::LessEqual => LexemeKind::LessEqual,
+            Lexeme::DotDot => LexemeKind::DotDot,
+            Lexeme::Ellipsis => LexemeKind::Ellipsis,
+            Lexeme::Identifier(_) | Lexeme::Symbol(_) => LexemeKind::Identifier,
+            Lexeme::String(_) => LexemeKind::String,
+            Lexeme::Number(_) => LexemeKind::Number,
+            Lexeme::Comment { .. } => LexemeKind::Comment,
+            Lexeme::Regex(_) => LexemeKind::Regex,
+            Lexeme::Heredoc { .. } => LexemeKind::Heredoc,
+            Lexeme::And => LexemeKind::And,
+            Lexeme::Class => LexemeKind::Class,
+            Lexeme::Else => LexemeKind::Else,
+            Lexeme::False => LexemeKind::False,
+            Lexeme::Fun => LexemeKind::Fun,
+            Lexeme::For => LexemeKind::For,
+            Lexeme::If => LexemeKind::If,
+            Lexeme::Nil => LexemeKind::Nil,
+            Lexeme::Or => LexemeKind::Or,
+            Lexeme::Print => LexemeKind::Print,
+            Lexeme::Return => LexemeKind::Return,
+            Lexeme::Super => LexemeKind::Super,
+            Lexeme::This => LexemeKind::This,
+            Lexeme::True => LexemeKind::True,
+            Lexeme::Var => LexemeKind::Var,
+            Lexeme::While => LexemeKind::While,
+            Lexeme::Whitespace(_) => LexemeKind::Whitespace,
+            Lexeme::NewLine => LexemeKind::NewLine,
+            Lexeme::Bof => LexemeKind::Bof,
+            Lexeme::Eof => LexemeKind::Eof,
+        }
+    }
+}
+
+/// All the kinds, in the order they are declared.
+const KINDS: [LexemeKind; 47] = [
+    LexemeKind::LeftParen,
+    LexemeKind::RightParen,
+    LexemeKind::LeftBrace,
+    LexemeKind::RightBrace,
+    LexemeKind::Comma,
+    LexemeKind::Dot,
+    LexemeKind::Minus,
+    LexemeKind::Plus,
+    LexemeKind::Semicolon,
+    LexemeKind::Slash,
+    LexemeKind::Star,
+    LexemeKind::Bang,
+    LexemeKind::BangEqual,
+    LexemeKind::Equal,
+    LexemeKind::EqualEqual,
+    LexemeKind::Greater,
+    LexemeKind::GreaterEqual,
+    LexemeKind::Less,
+    LexemeKind::LessEqual,
+    LexemeKind::DotDot,
+    LexemeKind::Ellipsis,
+    LexemeKind::Identifier,
+    LexemeKind::String,
+    LexemeKind::Number,
+    LexemeKind::Comment,
+    LexemeKind::Regex,
+    LexemeKind::Heredoc,
+    LexemeKind::And,
+    LexemeKind::Class,
+    LexemeKind::Else,
+    LexemeKind::False,
+    LexemeKind::Fun,
+    LexemeKind::For,
+    LexemeKind::If,
+    LexemeKind::Nil,
+    LexemeKind::Or,
+    LexemeKind::Print,
+    LexemeKind::Return,
+    LexemeKind::Super,
+    LexemeKind::This,
+    LexemeKind::True,
+    LexemeKind::Var,
+    LexemeKind::While,
+    LexemeKind::Whitespace,
+    LexemeKind::NewLine,
+    LexemeKind::Bof,
+    LexemeKind::Eof,
+];
+
+impl LexemeKind {
+    /// Returns how the kind reads in a message: the text of the
+    /// fixed lexemes in backticks, or the name of the others.
+    fn describe(self) -> &'static str {
+        match self {
+            LexemeKind::LeftParen => "`(`",
+            LexemeKind::RightParen => "`)`",
+            LexemeKind::LeftBrace => "`{`",
+            LexemeKind::RightBrace => "`}`",
+            LexemeKind::Comma => "`,`",
+            LexemeKind::Dot => "`.`",
+            LexemeKind::Minus => "`-`",
+            LexemeKind::Plus => "`+`",
+            LexemeKind::Semicolon => "`;`",
+            LexemeKind::Slash => "`/`",
+            LexemeKind::Star => "`*`",
+            LexemeKind::Bang => "`!`",
+            LexemeKind::BangEqual => "`!=`",
+            LexemeKind::Equal => "`=`",
+            LexemeKind::EqualEqual => "`==`",
+            LexemeKind::Greater => "`>`",
+            LexemeKind::GreaterEqual => "`>=`",
+            LexemeKind::Less => "`<`",
+            LexemeKind::LessEqual => "`<=`",
+            LexemeKind::DotDot => "`..`",
+            LexemeKind::Ellipsis => "`...`",
+            LexemeKind::Identifier => "identifier",
+            LexemeKind::String => "string",
+            LexemeKind::Number => "number",
+            LexemeKind::Comment => "comment",
+            LexemeKind::Regex => "regex",
+            LexemeKind::Heredoc => "heredoc",
+            LexemeKind::And => "`and`",
+            LexemeKind::Class => "`class`",
+            LexemeKind::Else => "`else`",
+            LexemeKind::False => "`false`",
+            LexemeKind::Fun => "`fun`",
+            LexemeKind::For => "`for`",
+            LexemeKind::If => "`if`",
+            LexemeKind::Nil => "`nil`",
+            LexemeKind::Or => "`or`",
+            LexemeKind::Print => "`print`",
+            LexemeKind::Return => "`return`",
+            LexemeKind::Super => "`super`",
+            LexemeKind::This => "`this`",
+            LexemeKind::True => "`true`",
+            LexemeKind::Var => "`var`",
+            LexemeKind::While => "`while`",
+            LexemeKind::Whitespace => "whitespace",
+            LexemeKind::NewLine => "new line",
+            LexemeKind::Bof => "beginning of file",
+            LexemeKind::Eof => "end of file",
+        }
+    }
+}
+
+/// A set of lexeme kinds, e.g. the kinds a parser expects next.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LexemeKindSet(u64);
+
+impl LexemeKindSet {
+    /// Creates an empty set.
+    #[inline]
+    pub fn new() -> Self {
+        Self(0)
+    }
+
+    /// Adds a kind to the set.
+    #[inline]
+    pub fn insert(&mut self, kind: LexemeKind) {
+        self.0 |= 1 << kind as u64;
+    }
+
+    /// Checks if the set contains a kind.
+    #[inline]
+    pub fn contains(&self, kind: LexemeKind) -> bool {
+        self.0 & (1 << kind as u64) != 0
+    }
+
+    /// Returns the kinds which are in either set.
+    #[inline]
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Checks if the set is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the number of kinds in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Returns the kinds in the set, in the order they are declared.
+    pub fn iter(&self) -> impl Iterator<Item = LexemeKind> + '_ {
+        KINDS
+            .iter()
+            .copied()
+            .filter(move |kind| self.contains(*kind))
+    }
+}
+
+impl FromIterator<LexemeKind> for LexemeKindSet {
+    fn from_iter<I: IntoIterator<Item = LexemeKind>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for kind in iter {
+            set.insert(kind);
+        }
+        set
+    }
+}
+
+/// Lists the kinds, e.g. "`)`, `,`, or `;`".
+impl fmt::Display for LexemeKindSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let len = self.len();
+        for (i, kind) in self.iter().enumerate() {
+            if i > 0 && len > 2 {
+                write!(f, ",")?;
+            }
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            if i > 0 && i + 1 == len {
+                write!(f, "or ")?;
+            }
+            write!(f, "{}", kind.describe())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NumberLit;
+
+    #[test]
+    fn test_kind() {
+        assert_eq!(LexemeKind::LeftParen, Lexeme::LeftParen.kind());
+        assert_eq!(LexemeKind::While, Lexeme::While.kind());
+        assert_eq!(
+            LexemeKind::Identifier,
+            Lexeme::Identifier("x".to_string()).kind()
+        );
+        assert_eq!(
+            LexemeKind::Number,
+            Lexeme::Number(NumberLit::from_value(1.)).kind()
+        );
+        assert_eq!(LexemeKind::Eof, Lexeme::Eof.kind());
+    }
+
+    #[test]
+    fn test_kinds_fit_the_set() {
+        assert!(KINDS.len() <= 64);
+        for (i, kind) in KINDS.iter().enumerate() {
+            assert_eq!(i, *kind as usize);
+        }
+    }
+
+    #[test]
+    fn test_kind_set() {
+        let mut set = LexemeKindSet::new();
+        assert!(set.is_empty());
+
+        set.insert(LexemeKind::Semicolon);
+        set.insert(LexemeKind::Eof);
+        set.insert(LexemeKind::Semicolon);
+        assert_eq!(2, set.len());
+        assert!(set.contains(LexemeKind::Semicolon));
+        assert!(set.contains(LexemeKind::Eof));
+        assert!(!set.contains(LexemeKind::Comma));
+
+        let other: LexemeKindSet = vec![LexemeKind::Comma, LexemeKind::Eof]
+            .into_iter()
+            .collect();
+        let union = set.union(other);
+        assert_eq!(3, union.len());
+        assert_eq!(
+            vec![LexemeKind::Comma, LexemeKind::Semicolon, LexemeKind::Eof],
+            union.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_kind_set_display() {
+        let set = |kinds: &[LexemeKind]| kinds.iter().copied().collect::<LexemeKindSet>();
+
+        assert_eq!("", set(&[]).to_string());
+        assert_eq!("`;`", set(&[LexemeKind::Semicolon]).to_string());
+        assert_eq!(
+            "identifier or `var`",
+            set(&[LexemeKind::Var, LexemeKind::Identifier]).to_string()
+        );
+        assert_eq!(
+            "`)`, `,`, or `;`",
+            set(&[
+                LexemeKind::Semicolon,
+                LexemeKind::RightParen,
+                LexemeKind::Comma
+            ])
+            .to_string()
+        );
+    }
+}
@@ -1,8 +1,89 @@
 use std::fmt::{Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::mem::discriminant;
 use std::string::String;
 
+/// The payload of a number literal: the decoded value
+/// and the raw spelling it was scanned from.
+#[derive(Clone, Debug)]
+pub struct NumberLit {
+    /// The decoded value
+    pub value: f64,
+    /// The raw source spelling, e.g. `1e3` or `0123`
+    pub raw: Box<str>,
+}
+
+impl NumberLit {
+    /// Creates a number literal from its value and raw spelling.
+    #[inline]
+    pub fn new(value: f64, raw: &str) -> Self {
+        Self {
+            value,
+            raw: raw.into(),
+        }
+    }
+
+    /// Creates a number literal from its value only,
+    /// with the raw spelling synthesized from the value,
+    /// e.g. `10` for `10.0` and `-0` for `-0.0`.
+    #[inline]
+    pub fn from_value(value: f64) -> Self {
+        Self {
+            value,
+            raw: value.to_string().into_boxed_str(),
+        }
+    }
+}
+
+/// The literals are compared by their values only, like the former `f64`
+/// lexemes, so `1e3` and `1000` are equal whatever their raw spellings.
+/// The values are compared by their bits, so the literals can be used as keys.
+/// As a consequence `0.0` and `-0.0` are not equal, unlike the `f64` values;
+/// compare the `value` fields for the arithmetic equality. The lexer never
+/// scans a `-0.0`, the minus is a separate token, but an evaluator can build it.
+impl PartialEq for NumberLit {
+    fn eq(&self, other: &Self) -> bool {
+        self.value.to_bits() == other.value.to_bits()
+    }
+}
+
+impl Eq for NumberLit {}
+
+impl Hash for NumberLit {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.to_bits().hash(state);
+    }
+}
+
+/// The style of a comment, which tells its delimiters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CommentStyle {
+    /// A line comment, `// ...`
+    Line,
+    /// A block comment, `/* ... */`
+    Block,
+    /// A doc comment, `/// ...`
+    Doc,
+    /// A doc block comment, `/** ... */`
+    DocBlock,
+}
+
+impl CommentStyle {
+    /// Returns the text which opens and the text which closes a comment of
+    /// this style. A line comment is closed by its new line, which is kept
+    /// in the text of the comment.
+    pub fn delimiters(&self) -> (&'static str, &'static str) {
+        match self {
+            CommentStyle::Line => ("//", ""),
+            CommentStyle::Block => ("/*", "*/"),
+            CommentStyle::Doc => ("///", ""),
+            CommentStyle::DocBlock => ("/**", "*/"),
+        }
+    }
+}
+
 /// Represents the lexemes supported by the language.
-#[derive(Clone, PartialEq)]
+#[derive(Clone)]
 pub enum Lexeme {
     //
     // Single-char lexemes
@@ -48,17 +129,37 @@ pub enum Lexeme {
     Less,
     /// LessEqual
     LessEqual,
+    /// DotDot
+    DotDot,
+    /// Ellipsis
+    Ellipsis,
     //
     // Literals lexemes
     //
     /// Identity
     Identifier(String),
+    /// Identity, interned as a symbol (only with an interner)
+    Symbol(crate::Symbol),
     /// String
     String(String),
-    /// Number
-    Number(f64),
-    /// Comment
-    Comment(String),
+    /// Number, with its value and raw source spelling
+    Number(NumberLit),
+    /// Comment, with its text without the delimiters
+    Comment {
+        /// The text of the comment
+        text: String,
+        /// The style of the comment
+        style: CommentStyle,
+    },
+    /// Regex literal, without the slashes (only in the regex dialect)
+    Regex(String),
+    /// Heredoc, with its tag and the lines of its body (only with the heredocs)
+    Heredoc {
+        /// The tag which opens and closes the heredoc
+        tag: String,
+        /// The lines between the opening and the closing tags
+        body: String,
+    },
     //
     // Keywords lexemes
     //
@@ -101,12 +202,158 @@ pub enum Lexeme {
     Whitespace(String),
     /// New line
     NewLine,
+    /// BOF, only when requested
+    Bof,
     /// EOF
     Eof,
 }
 
 use Lexeme::*;
 
+impl Lexeme {
+    /// Returns the canonical spelling of a keyword lexeme,
+    /// or `None` if the lexeme is not a keyword.
+    pub fn canonical_keyword_text(&self) -> Option<&'static str> {
+        match self {
+            And => Some("and"),
+            Class => Some("class"),
+            Else => Some("else"),
+            False => Some("false"),
+            Fun => Some("fun"),
+            For => Some("for"),
+            If => Some("if"),
+            Nil => Some("nil"),
+            Or => Some("or"),
+            Print => Some("print"),
+            Return => Some("return"),
+            Super => Some("super"),
+            This => Some("this"),
+            True => Some("true"),
+            Var => Some("var"),
+            While => Some("while"),
+            _ => None,
+        }
+    }
+}
+
+impl Lexeme {
+    /// Returns the length in characters of a lexeme which has a fixed
+    /// spelling in the source, e.g. 1 for `(`, 2 for `==` and 5 for `while`,
+    /// or `None` for the lexemes with a variable length, e.g. identifiers.
+    pub fn canonical_len(&self) -> Option<usize> {
+        match self {
+            LeftParen | RightParen | LeftBrace | RightBrace | Comma | Dot | Minus | Plus
+            | Semicolon | Slash | Star | Bang | Equal | Greater | Less => Some(1),
+            BangEqual | EqualEqual | GreaterEqual | LessEqual | DotDot => Some(2),
+            Ellipsis => Some(3),
+            Bof | Eof => Some(0),
+            l => l.canonical_keyword_text().map(str::len),
+        }
+    }
+}
+
+impl Lexeme {
+    /// Creates a 'comment' lexeme of a given style.
+    #[inline]
+    pub fn comment(text: &str, style: CommentStyle) -> Self {
+        Comment {
+            text: text.to_string(),
+            style,
+        }
+    }
+
+    /// Creates a line 'comment' lexeme, the only style the lexer reads.
+    #[inline]
+    pub fn line_comment(text: &str) -> Self {
+        Self::comment(text, CommentStyle::Line)
+    }
+
+    /// Returns the text payload of the lexeme, as it was kept by the lexer,
+    /// or `None` for the lexemes without a text payload. Unlike the
+    /// formatted lexeme, the control characters are not escaped.
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            Identifier(s) | String(s) | Comment { text: s, .. } | Regex(s) | Whitespace(s) => {
+                Some(s)
+            }
+            Number(number) => Some(&number.raw),
+            Heredoc { body, .. } => Some(body),
+            _ => None,
+        }
+    }
+
+    /// Compares two lexemes as [PartialEq] does, except that any two
+    /// 'whitespace' lexemes are equal, whatever their content, e.g. to
+    /// compare two token streams which differ only by their spacing.
+    /// The 'new line' lexemes are still distinct from the whitespaces.
+    pub fn eq_ignoring_whitespace_content(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Whitespace(_), Whitespace(_)) => true,
+            _ => self == other,
+        }
+    }
+}
+
+/// Formats a text payload with its control characters escaped,
+/// e.g. a new line as `\n`, so the output stays on one line. The
+/// backslashes are escaped as well, as `\\`, so an escape kept raw
+/// in a string is told apart from an escaped control character.
+pub(crate) struct Escaped<'a>(pub(crate) &'a str);
+
+impl<'a> Display for Escaped<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for c in self.0.chars() {
+            if c.is_control() || c == '\\' {
+                write!(f, "{}", c.escape_default())?;
+            } else {
+                write!(f, "{}", c)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Numbers are compared by their bits, so the lexemes can be used as keys.
+impl PartialEq for Lexeme {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Identifier(a), Identifier(b)) => a == b,
+            (Symbol(a), Symbol(b)) => a == b,
+            (String(a), String(b)) => a == b,
+            (Number(a), Number(b)) => a == b,
+            (Comment { text: a, style: sa }, Comment { text: b, style: sb }) => a == b && sa == sb,
+            (Regex(a), Regex(b)) => a == b,
+            (Heredoc { tag: ta, body: ba }, Heredoc { tag: tb, body: bb }) => ta == tb && ba == bb,
+            (Whitespace(a), Whitespace(b)) => a == b,
+            _ => discriminant(self) == discriminant(other),
+        }
+    }
+}
+
+impl Eq for Lexeme {}
+
+impl Hash for Lexeme {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        discriminant(self).hash(state);
+
+        match self {
+            Identifier(s) | String(s) | Regex(s) | Whitespace(s) => s.hash(state),
+            Comment { text, style } => {
+                text.hash(state);
+                style.hash(state);
+            }
+            Heredoc { tag, body } => {
+                tag.hash(state);
+                body.hash(state);
+            }
+            Number(number) => number.hash(state),
+            Symbol(symbol) => symbol.hash(state),
+            _ => (),
+        }
+    }
+}
+
 impl Debug for Lexeme {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -129,10 +376,23 @@ impl Debug for Lexeme {
             GreaterEqual => write!(f, "GREATER_EQUAL"),
             Less => write!(f, "LESS"),
             LessEqual => write!(f, "LESS_EQUAL"),
+            DotDot => write!(f, "DOT_DOT"),
+            Ellipsis => write!(f, "ELLIPSIS"),
             Identifier(i) => write!(f, "IDENTITY({})", i),
-            String(string) => write!(f, "STRING({}", string),
-            Number(number) => write!(f, "NUMBER({})", number),
-            Comment(comment) => write!(f, "COMMENT({})", comment),
+            Symbol(symbol) => write!(f, "SYMBOL({})", symbol.0),
+            String(string) => write!(f, "STRING({})", Escaped(string)),
+            Number(number) => write!(f, "NUMBER({})", number.value),
+            Comment { text, style } => {
+                let name = match style {
+                    CommentStyle::Line => "COMMENT",
+                    CommentStyle::Block => "BLOCK_COMMENT",
+                    CommentStyle::Doc => "DOC_COMMENT",
+                    CommentStyle::DocBlock => "DOC_BLOCK_COMMENT",
+                };
+                write!(f, "{}({})", name, Escaped(text))
+            }
+            Regex(regex) => write!(f, "REGEX({})", regex),
+            Heredoc { tag, body } => write!(f, "HEREDOC({}:{})", tag, Escaped(body)),
             And => write!(f, "AND"),
             Class => write!(f, "CLASS"),
             Else => write!(f, "ELSE"),
@@ -149,65 +409,201 @@ impl Debug for Lexeme {
             True => write!(f, "TRUE"),
             Var => write!(f, "VAR"),
             While => write!(f, "WHILE"),
-            Whitespace(ws) => write!(f, "WHITESPACE({})", ws),
+            Whitespace(ws) => write!(f, "WHITESPACE({})", Escaped(ws)),
             NewLine => write!(f, "NEW_LINE"),
+            Bof => write!(f, "BOF"),
             Eof => write!(f, "EOF"),
         }
     }
 }
 
+/// Reverts the escapes written by [Escaped], e.g. `\n` back to a new line
+/// and `\\` back to a backslash. Any other backslash is kept verbatim.
+fn unescape(s: &str) -> Option<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.peek() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            Some('u') => {
+                chars.next();
+                if chars.next() != Some('{') {
+                    return None;
+                }
+                let hex: String = chars.by_ref().take_while(|h| *h != '}').collect();
+                out.push(std::char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                continue;
+            }
+            _ => {
+                out.push(c);
+                continue;
+            }
+        }
+        chars.next();
+    }
+
+    Some(out)
+}
+
+impl Lexeme {
+    /// Parses the `Debug` format of a lexeme back into the lexeme, e.g.
+    /// `IDENTITY(foo)`, `NUMBER(12.3)` or `AND`, so the expected tokens of
+    /// a test can be written in the terse debug syntax. Returns `None` if
+    /// the text is not the debug format of any lexeme.
+    ///
+    /// The debug format keeps only the value of a number, so its raw text
+    /// is the formatted value, as with [NumberLit::from_value]. A backslash
+    /// followed by `n`, `t`, `r` or `u{..}` in a text payload is read as an
+    /// escaped control character.
+    pub fn from_token_debug(s: &str) -> Option<Lexeme> {
+        let fixed = match s {
+            "L_PAREN" => LeftParen,
+            "R_PAREN" => RightParen,
+            "L_BRACE" => LeftBrace,
+            "R_BRACE" => RightBrace,
+            "COMMA" => Comma,
+            "DOT" => Dot,
+            "MINUS" => Minus,
+            "PLUS" => Plus,
+            "SEMICOLON" => Semicolon,
+            "SLASH" => Slash,
+            "STAR" => Star,
+            "BANG" => Bang,
+            "BANG_EQUAL" => BangEqual,
+            "EQUAL" => Equal,
+            "EQUAL_EQUAL" => EqualEqual,
+            "GREATER" => Greater,
+            "GREATER_EQUAL" => GreaterEqual,
+            "LESS" => Less,
+            "LESS_EQUAL" => LessEqual,
+            "DOT_DOT" => DotDot,
+            "ELLIPSIS" => Ellipsis,
+            "AND" => And,
+            "CLASS" => Class,
+            "ELSE" => Else,
+            "FALSE" => False,
+            "FUN" => Fun,
+            "FOR" => For,
+            "IF" => If,
+            "NIL" => Nil,
+            "OR" => Or,
+            "PRINT" => Print,
+            "RETURN" => Return,
+            "SUPER" => Super,
+            "THIS" => This,
+            "TRUE" => True,
+            "VAR" => Var,
+            "WHILE" => While,
+            "NEW_LINE" => NewLine,
+            "BOF" => Bof,
+            "EOF" => Eof,
+            _ => return Self::from_payload_debug(s),
+        };
+
+        Some(fixed)
+    }
+
+    /// Parses the `Debug` format of a lexeme with a payload.
+    fn from_payload_debug(s: &str) -> Option<Lexeme> {
+        let (name, payload) = s.split_at(s.find('(')?);
+        let payload = payload[1..].strip_suffix(')')?;
+
+        let lexeme = match name {
+            "IDENTITY" => Identifier(payload.to_string()),
+            "STRING" => String(unescape(payload)?),
+            "SYMBOL" => Symbol(crate::Symbol(payload.parse().ok()?)),
+            "NUMBER" => Number(NumberLit::from_value(payload.parse().ok()?)),
+            "COMMENT" => Self::comment(&unescape(payload)?, CommentStyle::Line),
+            "BLOCK_COMMENT" => Self::comment(&unescape(payload)?, CommentStyle::Block),
+            "DOC_COMMENT" => Self::comment(&unescape(payload)?, CommentStyle::Doc),
+            "DOC_BLOCK_COMMENT" => Self::comment(&unescape(payload)?, CommentStyle::DocBlock),
+            "REGEX" => Regex(payload.to_string()),
+            "HEREDOC" => {
+                let (tag, body) = payload.split_once(':')?;
+                Heredoc {
+                    tag: tag.to_string(),
+                    body: unescape(body)?,
+                }
+            }
+            "WHITESPACE" => Whitespace(unescape(payload)?),
+            _ => return None,
+        };
+
+        Some(lexeme)
+    }
+
+    /// Returns the fixed formatted text of a lexeme without a payload, the
+    /// same as its `Display` output, e.g. `==`, `while` or `eof`, or `None`
+    /// for the lexemes with a payload. It needs no formatter, so the text
+    /// can be pushed directly into a string.
+    pub fn static_str(&self) -> Option<&'static str> {
+        match self {
+            LeftParen => Some("("),
+            RightParen => Some(")"),
+            LeftBrace => Some("{"),
+            RightBrace => Some("}"),
+            Comma => Some(","),
+            Dot => Some("."),
+            Minus => Some("-"),
+            Plus => Some("+"),
+            Semicolon => Some(";"),
+            Slash => Some("/"),
+            Star => Some("*"),
+            Bang => Some("!"),
+            BangEqual => Some("!="),
+            Equal => Some("="),
+            EqualEqual => Some("=="),
+            Greater => Some(">"),
+            GreaterEqual => Some(">="),
+            Less => Some("<"),
+            LessEqual => Some("<="),
+            DotDot => Some(".."),
+            Ellipsis => Some("..."),
+            NewLine => Some("nl"),
+            Bof => Some("bof"),
+            Eof => Some("eof"),
+            l => l.canonical_keyword_text(),
+        }
+    }
+}
+
 impl Display for Lexeme {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            LeftParen => write!(f, "("),
-            RightParen => write!(f, ")"),
-            LeftBrace => write!(f, "{{"),
-            RightBrace => write!(f, "}}"),
-            Comma => write!(f, ","),
-            Dot => write!(f, "."),
-            Minus => write!(f, "-"),
-            Plus => write!(f, "+"),
-            Semicolon => write!(f, ";"),
-            Slash => write!(f, "/"),
-            Star => write!(f, "*"),
-            Bang => write!(f, "!"),
-            BangEqual => write!(f, "!="),
-            Equal => write!(f, "="),
-            EqualEqual => write!(f, "=="),
-            Greater => write!(f, ">"),
-            GreaterEqual => write!(f, ">="),
-            Less => write!(f, "<"),
-            LessEqual => write!(f, "<="),
             Identifier(i) => write!(f, "id({})", i),
-            String(string) => write!(f, "str({}", string),
-            Number(number) => write!(f, "num({})", number),
-            Comment(comment) => write!(f, "cmt({})", comment),
-            And => write!(f, "and"),
-            Class => write!(f, "class"),
-            Else => write!(f, "else"),
-            False => write!(f, "false"),
-            Fun => write!(f, "fun"),
-            For => write!(f, "for"),
-            If => write!(f, "if"),
-            Nil => write!(f, "nil"),
-            Or => write!(f, "or"),
-            Print => write!(f, "print"),
-            Return => write!(f, "return"),
-            Super => write!(f, "super"),
-            This => write!(f, "this"),
-            True => write!(f, "true"),
-            Var => write!(f, "var"),
-            While => write!(f, "while"),
-            Whitespace(ws) => write!(f, "ws({})", ws),
-            NewLine => write!(f, "nl"),
-            Eof => write!(f, "eof"),
+            Symbol(symbol) => write!(f, "sym({})", symbol.0),
+            String(string) => write!(f, "str({})", Escaped(string)),
+            Number(number) => write!(f, "num({})", number.raw),
+            Comment { text, style } => {
+                let name = match style {
+                    CommentStyle::Line => "cmt",
+                    CommentStyle::Block => "blk",
+                    CommentStyle::Doc => "doc",
+                    CommentStyle::DocBlock => "docblk",
+                };
+                write!(f, "{}({})", name, Escaped(text))
+            }
+            Regex(regex) => write!(f, "re({})", regex),
+            Heredoc { tag, body } => write!(f, "here({}:{})", tag, Escaped(body)),
+            Whitespace(ws) => write!(f, "ws({})", Escaped(ws)),
+            l => f.write_str(l.static_str().unwrap_or_default()),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Lexeme;
+    use crate::{CommentStyle, Lexeme, Lexer, NumberLit, Token};
+    use std::collections::HashSet;
 
     fn test_lexeme(l: Lexeme) {
         let s = format!("{}", l);
@@ -247,6 +643,16 @@ mod tests {
         test_lexeme(Lexeme::Dot);
     }
 
+    #[test]
+    fn test_debug_dot_dot() {
+        test_lexeme(Lexeme::DotDot);
+    }
+
+    #[test]
+    fn test_debug_ellipsis() {
+        test_lexeme(Lexeme::Ellipsis);
+    }
+
     #[test]
     fn test_debug_plus() {
         test_lexeme(Lexeme::Plus);
@@ -324,12 +730,191 @@ mod tests {
 
     #[test]
     fn test_debug_comment() {
-        test_lexeme(Lexeme::Comment("hello".to_string()));
+        test_lexeme(Lexeme::line_comment("hello"));
+    }
+
+    #[test]
+    fn test_from_token_debug_round_trip() {
+        let lexemes = vec![
+            Lexeme::LeftParen,
+            Lexeme::RightParen,
+            Lexeme::LeftBrace,
+            Lexeme::RightBrace,
+            Lexeme::Comma,
+            Lexeme::Dot,
+            Lexeme::Minus,
+            Lexeme::Plus,
+            Lexeme::Semicolon,
+            Lexeme::Slash,
+            Lexeme::Star,
+            Lexeme::Bang,
+            Lexeme::BangEqual,
+            Lexeme::Equal,
+            Lexeme::EqualEqual,
+            Lexeme::Greater,
+            Lexeme::GreaterEqual,
+            Lexeme::Less,
+            Lexeme::LessEqual,
+            Lexeme::DotDot,
+            Lexeme::Ellipsis,
+            Lexeme::Identifier("foo".to_string()),
+            Lexeme::Symbol(crate::Symbol(7)),
+            Lexeme::String("hi (there)".to_string()),
+            Lexeme::String("a\nb\t\u{1}".to_string()),
+            Lexeme::String(String::new()),
+            Lexeme::Number(NumberLit::from_value(12.3)),
+            Lexeme::Number(NumberLit::from_value(-0.)),
+            Lexeme::line_comment(" one\n"),
+            Lexeme::comment(" a\n b ", CommentStyle::Block),
+            Lexeme::comment(" doc\n", CommentStyle::Doc),
+            Lexeme::comment(" a\n b ", CommentStyle::DocBlock),
+            Lexeme::Regex("a+b".to_string()),
+            Lexeme::Heredoc {
+                tag: "END".to_string(),
+                body: "a: 1\nb\n".to_string(),
+            },
+            Lexeme::And,
+            Lexeme::Class,
+            Lexeme::Else,
+            Lexeme::False,
+            Lexeme::Fun,
+            Lexeme::For,
+            Lexeme::If,
+            Lexeme::Nil,
+            Lexeme::Or,
+            Lexeme::Print,
+            Lexeme::Return,
+            Lexeme::Super,
+            Lexeme::This,
+            Lexeme::True,
+            Lexeme::Var,
+            Lexeme::While,
+            Lexeme::Whitespace(" \t\r".to_string()),
+            Lexeme::NewLine,
+            Lexeme::Bof,
+            Lexeme::Eof,
+        ];
+
+        for lexeme in lexemes {
+            let debug = format!("{:?}", lexeme);
+            assert_eq!(Some(lexeme), Lexeme::from_token_debug(&debug), "{}", debug);
+        }
+    }
+
+    #[test]
+    fn test_from_token_debug_raw_escapes() {
+        let tkns: Vec<Token> = Lexer::with_source(r#"x = "a\nb\\c";"#).collect();
+        let string = &tkns[4].lexeme;
+        assert_eq!(&Lexeme::String(r"a\nb\\c".to_string()), string);
+
+        let debug = format!("{:?}", string);
+        assert_eq!(Some(string.clone()), Lexeme::from_token_debug(&debug));
+
+        let newline = Lexeme::String("a\nb\\".to_string());
+        let debug = format!("{:?}", newline);
+        assert_eq!(Some(newline), Lexeme::from_token_debug(&debug));
+    }
+
+    #[test]
+    fn test_from_token_debug_invalid() {
+        assert_eq!(None, Lexeme::from_token_debug("WHAT"));
+        assert_eq!(None, Lexeme::from_token_debug("IDENTITY(foo"));
+        assert_eq!(None, Lexeme::from_token_debug("STRING(hi"));
+        assert_eq!(
+            Some(Lexeme::String("hi".to_string())),
+            Lexeme::from_token_debug("STRING(hi)")
+        );
+        assert_eq!(None, Lexeme::from_token_debug("NUMBER(abc)"));
+        assert_eq!(None, Lexeme::from_token_debug("UNKNOWN(x)"));
+        assert_eq!(
+            Some(Lexeme::Number(NumberLit::from_value(12.5))),
+            Lexeme::from_token_debug("NUMBER(12.5)")
+        );
+    }
+
+    #[test]
+    fn test_comment_styles() {
+        let line = Lexeme::line_comment(" one");
+        assert_eq!(Lexeme::comment(" one", CommentStyle::Line), line);
+        assert_ne!(Lexeme::comment(" one", CommentStyle::Doc), line);
+        assert_eq!(Some(" one"), line.text());
+
+        let cases = vec![
+            (CommentStyle::Line, "COMMENT(x)", "cmt(x)"),
+            (CommentStyle::Block, "BLOCK_COMMENT(x)", "blk(x)"),
+            (CommentStyle::Doc, "DOC_COMMENT(x)", "doc(x)"),
+            (CommentStyle::DocBlock, "DOC_BLOCK_COMMENT(x)", "docblk(x)"),
+        ];
+        for (style, debug, display) in cases {
+            let comment = Lexeme::comment("x", style);
+            assert_eq!(debug, format!("{:?}", comment));
+            assert_eq!(display, format!("{}", comment));
+        }
+    }
+
+    #[test]
+    fn test_comment_delimiters() {
+        assert_eq!(("//", ""), CommentStyle::Line.delimiters());
+        assert_eq!(("/*", "*/"), CommentStyle::Block.delimiters());
+        assert_eq!(("///", ""), CommentStyle::Doc.delimiters());
+        assert_eq!(("/**", "*/"), CommentStyle::DocBlock.delimiters());
+    }
+
+    #[test]
+    fn test_escaped_control_chars() {
+        let string = Lexeme::String("a\nb\tc".to_string());
+
+        assert_eq!("STRING(a\\nb\\tc)", format!("{:?}", string));
+        assert_eq!("str(a\\nb\\tc)", format!("{}", string));
+        assert_eq!(
+            "COMMENT( one\\n)",
+            format!("{:?}", Lexeme::line_comment(" one\n"))
+        );
+        assert_eq!(
+            "ws(\\t\\r)",
+            format!("{}", Lexeme::Whitespace("\t\r".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_text() {
+        let string = Lexeme::String("a\nb".to_string());
+
+        assert_eq!(Some("a\nb"), string.text());
+        assert_eq!(
+            Some("1.50"),
+            Lexeme::Number(NumberLit::new(1.5, "1.50")).text()
+        );
+        assert_eq!(
+            Some("1.5"),
+            Lexeme::Number(NumberLit::from_value(1.5)).text()
+        );
+        assert_eq!(None, Lexeme::While.text());
+    }
+
+    #[test]
+    fn test_canonical_len() {
+        assert_eq!(Some(1), Lexeme::LeftParen.canonical_len());
+        assert_eq!(Some(2), Lexeme::EqualEqual.canonical_len());
+        assert_eq!(Some(5), Lexeme::While.canonical_len());
+        assert_eq!(Some(0), Lexeme::Eof.canonical_len());
+        assert_eq!(None, Lexeme::Identifier("x".to_string()).canonical_len());
+        assert_eq!(None, Lexeme::String("x".to_string()).canonical_len());
+        assert_eq!(
+            None,
+            Lexeme::Number(NumberLit::from_value(1.)).canonical_len()
+        );
+        assert_eq!(None, Lexeme::NewLine.canonical_len());
+    }
+
+    #[test]
+    fn test_debug_regex() {
+        test_lexeme(Lexeme::Regex("ab+".to_string()));
     }
 
     #[test]
     fn test_debug_number() {
-        test_lexeme(Lexeme::Number(12.3));
+        test_lexeme(Lexeme::Number(NumberLit::from_value(12.3)));
     }
 
     #[test]
@@ -426,4 +1011,147 @@ mod tests {
     fn test_debug_eof() {
         test_lexeme(Lexeme::Eof);
     }
+
+    #[test]
+    fn test_debug_bof() {
+        test_lexeme(Lexeme::Bof);
+    }
+
+    #[test]
+    fn test_display_number_raw() {
+        let l = Lexeme::Number(NumberLit::new(10., "10.0"));
+        assert_eq!("num(10.0)", format!("{}", l));
+
+        let l = Lexeme::Number(NumberLit::new(1000., "1e3"));
+        assert_eq!("num(1e3)", format!("{}", l));
+    }
+
+    #[test]
+    fn test_display_number_from_value() {
+        let l = Lexeme::Number(NumberLit::from_value(10.));
+        assert_eq!("num(10)", format!("{}", l));
+    }
+
+    #[test]
+    fn test_display_negative_zero() {
+        let l = Lexeme::Number(NumberLit::from_value(-0.0));
+        assert_eq!("num(-0)", format!("{}", l));
+        assert_eq!("NUMBER(-0)", format!("{:?}", l));
+
+        let l = Lexeme::Number(NumberLit::from_value(0.0));
+        assert_eq!("num(0)", format!("{}", l));
+    }
+
+    #[test]
+    fn test_eq_negative_zero() {
+        let zero = NumberLit::from_value(0.0);
+        let negative_zero = NumberLit::from_value(-0.0);
+
+        assert_ne!(zero, negative_zero);
+        assert_eq!(zero.value, negative_zero.value);
+        assert_ne!(Lexeme::Number(zero), Lexeme::Number(negative_zero));
+    }
+
+    #[test]
+    fn test_eq() {
+        assert_eq!(Lexeme::Var, Lexeme::Var);
+        assert_ne!(Lexeme::Var, Lexeme::While);
+        assert_eq!(
+            Lexeme::Number(NumberLit::from_value(1.5)),
+            Lexeme::Number(NumberLit::from_value(1.5))
+        );
+        assert_ne!(
+            Lexeme::Number(NumberLit::from_value(1.5)),
+            Lexeme::Number(NumberLit::from_value(2.5))
+        );
+        assert_ne!(
+            Lexeme::Identifier("a".to_string()),
+            Lexeme::String("a".to_string())
+        );
+    }
+
+    #[test]
+    fn test_eq_ignores_raw() {
+        let exponent = NumberLit::new(1000.0, "1e3");
+        let plain = NumberLit::new(1000.0, "1000");
+
+        assert_eq!(exponent, plain);
+        assert_eq!(Lexeme::Number(exponent), Lexeme::Number(plain));
+
+        let mut set = HashSet::new();
+        set.insert(NumberLit::new(1000.0, "1e3"));
+        set.insert(NumberLit::new(1000.0, "1000"));
+        assert_eq!(1, set.len());
+    }
+
+    #[test]
+    fn test_hash() {
+        let mut set = HashSet::new();
+        set.insert(Lexeme::Var);
+        set.insert(Lexeme::Var);
+        set.insert(Lexeme::Number(NumberLit::from_value(1.5)));
+        set.insert(Lexeme::Number(NumberLit::from_value(1.5)));
+        set.insert(Lexeme::Identifier("a".to_string()));
+
+        assert_eq!(3, set.len());
+        assert!(set.contains(&Lexeme::Number(NumberLit::from_value(1.5))));
+    }
+
+    #[test]
+    fn test_static_str() {
+        use Lexeme::*;
+
+        #[rustfmt::skip]
+        let fixed = vec![
+            LeftParen, RightParen, LeftBrace, RightBrace, Comma, Dot, Minus, Plus, Semicolon,
+            Slash, Star, Bang, BangEqual, Equal, EqualEqual, Greater, GreaterEqual, Less,
+            LessEqual, DotDot, Ellipsis, And, Class, Else, False, Fun, For, If, Nil, Or, Print, Return,
+            Super, This, True, Var, While, NewLine, Bof, Eof,
+        ];
+        for l in fixed {
+            assert_eq!(Some(format!("{}", l).as_str()), l.static_str());
+        }
+
+        assert_eq!(Some("{"), LeftBrace.static_str());
+        assert_eq!(Some(">="), GreaterEqual.static_str());
+        assert_eq!(Some("while"), While.static_str());
+        assert_eq!(Some("nl"), NewLine.static_str());
+
+        assert_eq!(None, Identifier("x".to_string()).static_str());
+        assert_eq!(None, Number(NumberLit::from_value(1.)).static_str());
+        assert_eq!(None, Whitespace(" ".to_string()).static_str());
+    }
+
+    #[test]
+    fn test_eq_ignoring_whitespace_content() {
+        use Lexeme::*;
+
+        let spaces = Whitespace("  ".to_string());
+        let tab = Whitespace("\t".to_string());
+
+        assert_ne!(spaces, tab);
+        assert!(spaces.eq_ignoring_whitespace_content(&tab));
+        assert!(!spaces.eq_ignoring_whitespace_content(&NewLine));
+        assert!(Plus.eq_ignoring_whitespace_content(&Plus));
+        assert!(!Identifier("a".to_string())
+            .eq_ignoring_whitespace_content(&Identifier("b".to_string())));
+    }
+
+    #[test]
+    fn test_canonical_keyword_text() {
+        assert_eq!(Some("while"), Lexeme::While.canonical_keyword_text());
+        assert_eq!(Some("and"), Lexeme::And.canonical_keyword_text());
+        assert_eq!(Some("var"), Lexeme::Var.canonical_keyword_text());
+    }
+
+    #[test]
+    fn test_canonical_keyword_text_not_keyword() {
+        assert_eq!(None, Lexeme::LeftParen.canonical_keyword_text());
+        assert_eq!(None, Lexeme::EqualEqual.canonical_keyword_text());
+        assert_eq!(
+            None,
+            Lexeme::Identifier("while".to_string()).canonical_keyword_text()
+        );
+        assert_eq!(None, Lexeme::Eof.canonical_keyword_text());
+    }
 }
@@ -1,8 +1,15 @@
-use std::iter::Peekable;
-use std::str::{Chars, FromStr};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::str::{FromStr, Utf8Error};
 
 use crate::chars::*;
-use crate::{Lexeme, Span, Token};
+use crate::options::Options;
+use crate::source::Source;
+use crate::{
+    CharClassifier, Column, CommentStyle, EscapePolicy, Interner, LexError, LexWarning, Lexeme,
+    LexemeKind, Line, NumberLit, OverflowMode, PositionEncoding, SourceMap, Span, Token,
+};
 
 static KEYWORDS: &[(&str, Lexeme)] = &[
     ("and", Lexeme::And),
@@ -23,31 +30,176 @@ static KEYWORDS: &[(&str, Lexeme)] = &[
     ("while", Lexeme::While),
 ];
 
-struct Context<'a> {
-    source: Peekable<Chars<'a>>, // the source of characters
-    span: Span,                  // the active span
-    eof_generated: bool,         // flag indicating if the eof was generated or not
+/// The operators which may be followed by a second character,
+/// when they stand alone.
+static OPERATORS: &[(char, Lexeme)] = &[
+    (CHAR_BANG, Lexeme::Bang),
+    (CHAR_EQUAL, Lexeme::Equal),
+    (CHAR_GREATER, Lexeme::Greater),
+    (CHAR_LESS, Lexeme::Less),
+];
+
+/// The two-char operators, by their first and second characters.
+static OPERATOR_PAIRS: &[((char, char), Lexeme)] = &[
+    ((CHAR_BANG, CHAR_EQUAL), Lexeme::BangEqual),
+    ((CHAR_EQUAL, CHAR_EQUAL), Lexeme::EqualEqual),
+    ((CHAR_GREATER, CHAR_EQUAL), Lexeme::GreaterEqual),
+    ((CHAR_LESS, CHAR_EQUAL), Lexeme::LessEqual),
+];
+
+/// The number of tokens between two calls of the progress callback.
+const PROGRESS_INTERVAL: usize = 1024;
+
+pub(crate) struct Context<'a> {
+    pub(crate) text: Option<&'a str>, // the source string, if any
+    source: Source<'a>,               // the source of characters
+    pub(crate) span: Span,            // the active span
+    pub(crate) bytes_read: usize,     // the number of bytes read so far
+    pub(crate) bytes_total: usize,    // the length of the source in bytes, if known
+    bof_generated: bool,              // flag indicating if the bof was generated or not
+    eof_generated: bool,              // flag indicating if the eof was generated or not
+    buffer: String,                   // the text payload of the token being read
+    errors: Vec<LexError>,            // the errors reported so far
+    warnings: Vec<LexWarning>,        // the warnings reported so far
+    long_lines: Vec<(usize, usize)>,  // the lines longer than the maximum length
+    recovered: Vec<Span>,             // the regions skipped to recover from the errors
+    prev_kind: Option<LexemeKind>,    // the kind of the previous significant token
+    open_parens: usize,               // the number of parentheses not closed yet
+    last_line_end: Column,            // the column where the last new line was read
+    next_id: u32,                     // the id of the next token
+    origin: Span,                     // the empty span where the source starts
+    pub(crate) token_start: usize,    // the byte offset where the last token starts
+    pub(crate) options: Options,      // the options of the lexer
+    pub(crate) hooks: Hooks<'a>,      // the interner and the classifier, if any
+}
+
+/// The behaviours of the lexer which are supplied by the caller.
+#[derive(Default)]
+pub(crate) struct Hooks<'a> {
+    pub(crate) interner: Option<Box<dyn Interner + 'a>>, // interns the identifiers
+    pub(crate) classifier: Option<Box<dyn CharClassifier + 'a>>, // classifies the characters
 }
 
 impl<'a> Context<'a> {
     /// Creates a new context from a source string.
     pub(crate) fn new(source: &'a str) -> Self {
+        let mut ctx = Self::from_source(Source::from_str(source));
+        ctx.text = Some(source);
+        ctx.bytes_total = source.len();
+        ctx
+    }
+
+    /// Creates a new context from an iterator of characters.
+    pub(crate) fn from_chars(chars: impl Iterator<Item = char> + 'a) -> Self {
+        Self::from_source(Source::new(chars))
+    }
+
+    /// Creates a new context which reads its characters from a source.
+    fn from_source(source: Source<'a>) -> Self {
         Self {
-            source: source.chars().peekable(),
+            text: None,
+            source,
             span: Span::default(),
+            bytes_read: 0,
+            bytes_total: 0,
+            bof_generated: false,
             eof_generated: false,
+            buffer: String::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            long_lines: Vec::new(),
+            recovered: Vec::new(),
+            prev_kind: None,
+            open_parens: 0,
+            last_line_end: Column(0),
+            next_id: 0,
+            origin: Span::default(),
+            token_start: 0,
+            options: Options::default(),
+            hooks: Hooks::default(),
         }
     }
 
     /// Reads a new token from the source. the source is wrapped into a
     /// contenxt, which also can provide the span of the token.
     pub(crate) fn read_token(&mut self) -> Option<Token> {
-        if self.eof_generated {
-            None
-        } else if let Some(c) = self.read_char() {
-            self.read_token_with_char(c)
-        } else {
-            self.mk_eof_token()
+        let errors = self.errors.len();
+        let mut tkn = self.read_next_token();
+
+        if self.options.recover_to_statement {
+            if let Some(first) = (errors..self.errors.len()).find(|i| !self.errors[*i].is_lint()) {
+                tkn = self.recover_to_statement(first, tkn);
+            }
+        }
+
+        if let Some(tkn) = tkn.as_mut() {
+            tkn.source_id = self.options.source_id;
+            tkn.id = self.next_id;
+            self.next_id = self.next_id.wrapping_add(1);
+        }
+
+        tkn
+    }
+
+    /// Skips the tokens from an error until a statement boundary, a ';', a '}',
+    /// a new line or the eof, which is returned. Only the first error is kept,
+    /// the errors of the skipped tokens would be cascades of it, and the
+    /// skipped region is recorded as one span.
+    fn recover_to_statement(&mut self, first: usize, mut tkn: Option<Token>) -> Option<Token> {
+        let start = self.errors[first].span();
+        let mut end = start;
+
+        while let Some(t) = tkn.as_ref().filter(|t| {
+            !matches!(
+                t.lexeme,
+                Lexeme::Semicolon | Lexeme::RightBrace | Lexeme::NewLine | Lexeme::Eof
+            )
+        }) {
+            end = t.span;
+            tkn = self.read_next_token();
+        }
+
+        let cascades = self.errors.split_off(first + 1);
+        self.errors
+            .extend(cascades.into_iter().filter(LexError::is_lint));
+
+        let mut skipped = start;
+        skipped.end_line = end.end_line;
+        skipped.end_col = end.end_col;
+        self.recovered.push(skipped);
+
+        tkn
+    }
+
+    /// Reads the next token from the source.
+    fn read_next_token(&mut self) -> Option<Token> {
+        loop {
+            self.token_start = self.bytes_read;
+
+            if self.eof_generated {
+                return None;
+            } else if self.options.emit_bof && !self.bof_generated {
+                return self.mk_bof_token();
+            } else if let Some(c) = self.read_char() {
+                self.buffer.clear();
+
+                if let Some(tkn) = self.read_token_with_char(c) {
+                    match tkn.lexeme {
+                        Lexeme::Whitespace(_) | Lexeme::NewLine | Lexeme::Comment { .. } => (),
+                        _ => self.prev_kind = Some(tkn.lexeme.kind()),
+                    }
+
+                    match tkn.lexeme {
+                        Lexeme::LeftParen => self.open_parens += 1,
+                        Lexeme::RightParen => self.open_parens = self.open_parens.saturating_sub(1),
+                        _ => (),
+                    }
+
+                    return Some(tkn);
+                }
+            } else {
+                return self.mk_eof_token();
+            }
         }
     }
 
@@ -55,9 +207,49 @@ impl<'a> Context<'a> {
     /// If we have a regular character, only the column is incremented.
     /// If the character is a new line, then we increment the line.
     fn update_span(&mut self, c: char) {
-        self.span.incr_col();
+        self.bytes_read += c.len_utf8();
+
+        if c == CHAR_NEWLINE {
+            self.check_line_len();
+            self.last_line_end = self.span.end_col;
+        }
+
+        let width = self.options.position_encoding.width(c);
+        self.span.incr_col_n(width);
         if c == CHAR_NEWLINE {
             self.span.incr_line();
+        } else {
+            self.lint_line_len(width);
+        }
+    }
+
+    /// Reports a [LexError::LineTooLong] when the character just read, of
+    /// the given width, is the first one past the limit, so once per offending
+    /// line. The span covers this character.
+    fn lint_line_len(&mut self, width: usize) {
+        if let Some(limit) = self.options.lint_line_len {
+            let end = self.span.end_col.0;
+            if end > limit && end - width <= limit {
+                let line = self.span.end_line.0;
+                let span = Span {
+                    start_line: Line(line),
+                    start_col: Column(end - width),
+                    end_line: Line(line),
+                    end_col: Column(end),
+                };
+                self.errors
+                    .push(LexError::LineTooLong { line, limit, span });
+            }
+        }
+    }
+
+    /// Records the current line if it is longer than the maximum length.
+    fn check_line_len(&mut self) {
+        if let Some(max) = self.options.max_line_len {
+            let len = self.span.end_col.0;
+            if len > max {
+                self.long_lines.push((self.span.end_line.0, len));
+            }
         }
     }
 
@@ -71,10 +263,15 @@ impl<'a> Context<'a> {
         }
     }
 
+    /// Peeks the character which follows the next one, without consuming anything.
+    fn peek_second(&mut self) -> Option<char> {
+        self.source.peek_nth(1)
+    }
+
     /// Consumes a character only if it is equal with a given char.
     fn read_char_if(&mut self, c: char) -> bool {
         if let Some(c1) = self.source.peek() {
-            if c == *c1 {
+            if c == c1 {
                 let _ = self.read_char();
                 true
             } else {
@@ -85,133 +282,314 @@ impl<'a> Context<'a> {
         }
     }
 
-    /// Read until the end of the line
-    fn read_line(&mut self) -> String {
-        let mut buffer = String::new();
+    /// Returns the text payload of the token which was just read.
+    #[cfg_attr(not(feature = "arena"), allow(dead_code))]
+    pub(crate) fn payload(&self) -> &str {
+        &self.buffer
+    }
+
+    /// Takes the text payload of the token being read. When the payloads
+    /// are buffered, the text stays in the buffer and an empty string is returned.
+    fn take_payload(&mut self) -> String {
+        if self.options.buffered_payloads {
+            String::new()
+        } else {
+            std::mem::take(&mut self.buffer)
+        }
+    }
 
-        for c in &mut self.source {
-            self.span.incr_col();
-            buffer.push(c);
+    /// Read until the end of the line
+    fn read_line(&mut self) {
+        while let Some(c) = self.read_char() {
+            self.buffer.push(c);
 
             if c == CHAR_NEWLINE {
-                self.span.incr_line();
                 break;
             }
         }
+    }
+
+    /// Checks if a character starts an identifier.
+    fn is_ident_start(&self, c: char) -> bool {
+        match &self.hooks.classifier {
+            Some(classifier) => classifier.is_ident_start(c),
+            None => is_alpha(c),
+        }
+    }
+
+    /// Checks if a character continues an identifier.
+    fn is_ident_continue(&self, c: char) -> bool {
+        match &self.hooks.classifier {
+            Some(classifier) => classifier.is_ident_continue(c),
+            None => is_alphanum(c),
+        }
+    }
 
-        buffer
+    /// Checks if a character is a whitespace, other than the new line.
+    fn is_ws(&self, c: char) -> bool {
+        match &self.hooks.classifier {
+            Some(classifier) => classifier.is_whitespace(c),
+            None => is_whitespace(c),
+        }
     }
 
     /// Reads the sequence of whitespaces.
-    fn read_ws(&mut self, first_ws: char) -> String {
-        let mut buffer = format!("{}", first_ws);
+    fn read_ws(&mut self, first_ws: char) {
+        self.buffer.push(first_ws);
 
-        while let Some(maybe_ws) = self.source.peek().copied() {
-            if is_whitespace(maybe_ws) {
-                buffer.push(maybe_ws);
+        while let Some(maybe_ws) = self.source.peek() {
+            if self.is_ws(maybe_ws) && !self.is_crlf_at(0) {
+                self.buffer.push(maybe_ws);
                 self.read_char();
             } else {
                 break;
             }
         }
-
-        buffer
     }
 
-    /// Reads a string separated by the quotes.
-    fn read_string(&mut self) -> Option<String> {
-        let mut buffer = String::new();
-        let mut string_terminated = false;
+    /// Checks if a "\r\n" starts at the n-th character ahead, when
+    /// it is read as a new line.
+    fn is_crlf_at(&mut self, n: usize) -> bool {
+        self.options.crlf_newlines
+            && self.source.peek_nth(n) == Some(CHAR_CARRIAGE_RETURN)
+            && self.source.peek_nth(n + 1) == Some(CHAR_NEWLINE)
+    }
 
-        for c in &mut self.source {
-            self.span.incr_col();
-            if c == CHAR_NEWLINE {
-                self.span.incr_line();
-            }
+    /// Reads the sequence of whitespaces and new lines.
+    fn read_ws_and_newlines(&mut self, first_ws: char) {
+        self.buffer.push(first_ws);
 
-            if c == CHAR_DOUBLE_QUOTE {
-                string_terminated = true;
+        while let Some(maybe_ws) = self.source.peek() {
+            if self.is_ws(maybe_ws) || maybe_ws == CHAR_NEWLINE {
+                self.buffer.push(maybe_ws);
+                self.read_char();
+            } else {
                 break;
             }
-
-            buffer.push(c);
         }
+    }
 
-        if string_terminated {
-            Some(buffer)
-        } else {
-            None
+    /// Returns the escape policy of the strings opened by a delimiter,
+    /// or `None` if the character is not a string delimiter.
+    fn string_escapes(&self, delimiter: char) -> Option<EscapePolicy> {
+        match &self.options.string_delimiters {
+            None if delimiter == CHAR_DOUBLE_QUOTE => Some(self.options.escape_policy),
+            None => None,
+            Some(delimiters) => delimiters
+                .iter()
+                .find(|(d, _)| *d == delimiter)
+                .map(|(_, escapes)| *escapes),
         }
     }
 
-    /// Reads a number in float format.
-    fn read_number(&mut self, first_digit: char) -> Option<f64> {
-        let mut buffer = format!("{}", first_digit);
+    /// Reads a string until the delimiter which opened it.
+    /// Returns true if the string was terminated.
+    fn read_string(&mut self, delimiter: char, escapes: EscapePolicy) -> bool {
+        let mut string_terminated = false;
+        let decode = escapes == EscapePolicy::Decoded;
 
-        // Read leading digits
-        while let Some(maybe_digit) = self.source.peek().copied() {
-            if is_digit(maybe_digit) {
-                buffer.push(maybe_digit);
-                self.read_char();
-            } else {
+        while let Some(c) = self.read_char() {
+            if c == delimiter {
+                string_terminated = true;
                 break;
             }
-        }
 
-        // Try reading "." and the rest of the digits
-        if let Some(maybe_dot) = self.source.peek().copied() {
-            if maybe_dot == CHAR_DOT {
-                buffer.push(maybe_dot);
-                self.read_char();
+            if decode && c == CHAR_BACKSLASH {
+                self.read_escape(delimiter);
+            } else {
+                self.buffer.push(c);
+            }
+        }
 
-                let mut read_additional_digits = false;
+        string_terminated
+    }
 
-                while let Some(maybe_digit) = self.source.peek().copied() {
-                    if is_digit(maybe_digit) {
-                        buffer.push(maybe_digit);
+    /// Reads the escape sequence which follows a backslash and decodes it.
+    /// The delimiter of the string can be escaped as well.
+    /// An unknown or malformed escape is kept verbatim.
+    fn read_escape(&mut self, delimiter: char) {
+        match self.read_char() {
+            Some('n') => self.buffer.push('\n'),
+            Some('t') => self.buffer.push('\t'),
+            Some('r') => self.buffer.push('\r'),
+            Some('0') => self.buffer.push('\0'),
+            Some(CHAR_BACKSLASH) => self.buffer.push(CHAR_BACKSLASH),
+            Some(CHAR_DOUBLE_QUOTE) => self.buffer.push(CHAR_DOUBLE_QUOTE),
+            Some(c) if c == delimiter => self.buffer.push(c),
+            Some('u') if self.read_char_if(CHAR_LEFT_BRACE) => {
+                let mut hex = String::new();
+                while let Some(h) = self.source.peek() {
+                    if h.is_ascii_hexdigit() && hex.len() < 6 {
+                        hex.push(h);
                         self.read_char();
-                        read_additional_digits = true;
                     } else {
                         break;
                     }
                 }
 
-                // Lox does not support leading or trailing dot in
-                // number literals. This is not a valid number
-                // literal, if we encountered no digits after ".".
-                // Also note: we have to error here, because we
-                // already consumed at least the "." from the input
-                // and would have to "return" it if we didn't match
-                // something. Fortunately there is nothing in Lox yet
-                // that would require us to recover (e.g. methods on
-                // numbers -> "4.sqrt()")
-                if !read_additional_digits {
-                    return None;
+                let closed = self.read_char_if(CHAR_RIGHT_BRACE);
+                let decoded = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(std::char::from_u32);
+
+                match decoded {
+                    Some(c) if closed => self.buffer.push(c),
+                    _ => {
+                        self.buffer.push_str("\\u{");
+                        self.buffer.push_str(&hex);
+                        if closed {
+                            self.buffer.push(CHAR_RIGHT_BRACE);
+                        }
+                    }
+                }
+            }
+            Some(c) => {
+                self.buffer.push(CHAR_BACKSLASH);
+                self.buffer.push(c);
+            }
+            None => self.buffer.push(CHAR_BACKSLASH),
+        }
+    }
+
+    /// Reads a regex until the closing slash. An escaped slash does not
+    /// close the regex. Returns true if the regex was terminated.
+    fn read_regex(&mut self) -> bool {
+        while let Some(c) = self.source.peek() {
+            if c == CHAR_NEWLINE {
+                break;
+            }
+
+            self.read_char();
+            if c == CHAR_SLASH {
+                return true;
+            }
+
+            self.buffer.push(c);
+            if c == CHAR_BACKSLASH {
+                if let Some(escaped) = self.source.peek().filter(|e| *e != CHAR_NEWLINE) {
+                    self.buffer.push(escaped);
+                    self.read_char();
                 }
             }
         }
 
-        if let Ok(number) = f64::from_str(&buffer) {
-            Some(number)
+        false
+    }
+
+    /// Reads a number in float format. The raw spelling is kept in the buffer.
+    fn read_number(&mut self, first_digit: char) -> Option<f64> {
+        self.buffer.push(first_digit);
+
+        // Read leading digits
+        self.read_digits();
+
+        // With the decimal comma, a "," flanked by digits is the decimal
+        // separator, but only outside of the parentheses, where it can not
+        // separate the arguments or the parameters, e.g. in "f(1,2)".
+        if self.options.decimal_comma
+            && self.open_parens == 0
+            && self.source.peek() == Some(CHAR_COMMA)
+            && self.peek_second().is_some_and(is_digit)
+        {
+            self.buffer.push(CHAR_COMMA);
+            self.read_char();
+            self.read_digits();
+
+            let number = self.number_digits().replace(CHAR_COMMA, ".");
+            return f64::from_str(&number).ok();
+        }
+
+        // The "." belongs to the number only when it is followed by digits
+        // or by an enabled exponent, e.g. "1.5" or "1.e3". Otherwise it is
+        // left for a 'dot' token, e.g. "1.foo" is a method call.
+        let exponents = self.options.exponents;
+        if self.source.peek() == Some(CHAR_DOT)
+            && (self.peek_second().is_some_and(is_digit) || (exponents && self.is_exponent_at(1)))
+        {
+            self.buffer.push(CHAR_DOT);
+            self.read_char();
+            self.read_digits();
+        }
+
+        // Try reading the exponent, e.g. "1e3" or "2.5E-3"
+        if exponents && self.is_exponent_at(0) {
+            while let Some(c) = self.source.peek().filter(|c| !is_digit(*c)) {
+                self.buffer.push(c);
+                self.read_char();
+            }
+            self.read_digits();
+        }
+
+        f64::from_str(&self.number_digits()).ok()
+    }
+
+    /// Reads the digits which follow, into the buffer. With the digit
+    /// separators, the underscores are read as well.
+    fn read_digits(&mut self) {
+        let separators = self.options.digit_separators;
+        while let Some(digit) = self
+            .source
+            .peek()
+            .filter(|c| is_digit(*c) || (separators && *c == CHAR_UNDERSCORE))
+        {
+            self.buffer.push(digit);
+            self.read_char();
+        }
+    }
+
+    /// Returns the number in the buffer without its digit separators.
+    fn number_digits(&self) -> Cow<'_, str> {
+        if self.options.digit_separators && self.buffer.contains(CHAR_UNDERSCORE) {
+            Cow::Owned(self.buffer.replace(CHAR_UNDERSCORE, ""))
         } else {
-            None
+            Cow::Borrowed(&self.buffer)
+        }
+    }
+
+    /// Checks if every underscore in the buffer sits between two digits.
+    fn has_valid_separators(&self) -> bool {
+        if !self.buffer.contains(CHAR_UNDERSCORE) {
+            return true;
+        }
+
+        let chars: Vec<char> = self.buffer.chars().collect();
+        chars.iter().enumerate().all(|(i, c)| {
+            *c != CHAR_UNDERSCORE
+                || (i > 0
+                    && is_digit(chars[i - 1])
+                    && chars.get(i + 1).copied().is_some_and(is_digit))
+        })
+    }
+
+    /// Checks if an exponent starts at the n-th character ahead: an "e" or
+    /// "E", an optional sign and at least one digit. Without the digit, the
+    /// "e" is the start of an identifier, e.g. "1.else".
+    fn is_exponent_at(&mut self, n: usize) -> bool {
+        if !matches!(
+            self.source.peek_nth(n),
+            Some(CHAR_LOWERCASE_E) | Some(CHAR_UPPERCASE_E)
+        ) {
+            return false;
+        }
+
+        match self.source.peek_nth(n + 1) {
+            Some(CHAR_PLUS) | Some(CHAR_MINUS) => self.source.peek_nth(n + 2).is_some_and(is_digit),
+            c => c.is_some_and(is_digit),
         }
     }
 
     /// Reads an identifier
-    fn read_identifier(&mut self, first_alpha: char) -> String {
-        let mut buffer = format!("{}", first_alpha);
+    fn read_identifier(&mut self, first_alpha: char) {
+        self.buffer.push(first_alpha);
 
         while let Some(maybe_alphanumeric) = self.source.peek() {
-            if is_alphanum(*maybe_alphanumeric) {
-                buffer.push(*maybe_alphanumeric);
+            if self.is_ident_continue(maybe_alphanumeric) {
+                self.buffer.push(maybe_alphanumeric);
                 self.read_char();
             } else {
                 break;
             }
         }
-
-        buffer
     }
 
     /// Reads a token which starts with a given character.
@@ -222,25 +600,42 @@ impl<'a> Context<'a> {
             CHAR_LEFT_BRACE => self.mk_left_brace(),
             CHAR_RIGHT_BRACE => self.mk_right_brace(),
             CHAR_COMMA => self.mk_comma(),
-            CHAR_DOT => self.mk_dot(),
+            CHAR_DOT => self.mk_dots(),
             CHAR_PLUS => self.mk_plus(),
             CHAR_MINUS => self.mk_minus(),
             CHAR_SEMICOLON => self.mk_semicolon(),
             CHAR_STAR => self.mk_star(),
-            CHAR_BANG => self.mk_bang_or_bang_equal(),
-            CHAR_EQUAL => self.mk_equal_or_equal_equal(),
-            CHAR_GREATER => self.mk_greater_or_greater_equal(),
-            CHAR_LESS => self.mk_less_or_less_equal(),
+            CHAR_LESS if self.options.heredocs && self.is_heredoc_start() => self.mk_heredoc(),
+            CHAR_BANG | CHAR_EQUAL | CHAR_GREATER | CHAR_LESS => self.mk_operator(c),
             CHAR_SLASH => self.mk_slash_or_comment(),
+            ws if self.options.merge_whitespace_and_newlines
+                && (self.is_ws(ws) || ws == CHAR_NEWLINE) =>
+            {
+                self.mk_whitespace_and_newlines(ws)
+            }
             CHAR_NEWLINE => self.mk_newline(),
-            CHAR_DOUBLE_QUOTE => self.mk_string(),
-            ws if is_whitespace(ws) => self.mk_whitespace(ws),
+            CHAR_CARRIAGE_RETURN
+                if self.options.crlf_newlines && self.source.peek() == Some(CHAR_NEWLINE) =>
+            {
+                self.read_char();
+                self.mk_newline()
+            }
+            q if self.string_escapes(q).is_some() => self.mk_string(q),
+            ws if self.is_ws(ws) => self.mk_whitespace(ws),
+            a if self.is_ident_start(a) => self.mk_identifier_or_keyword(a),
             d if is_digit(d) => self.mk_number(d),
-            a if is_alpha(a) => self.mk_identifier_or_keyword(a),
-            unexpected => panic!("Unknown char {}", unexpected),
+            unexpected => self.skip_unexpected_char(unexpected),
         }
     }
 
+    /// Reports a character which does not start any token and skips it.
+    fn skip_unexpected_char(&mut self, c: char) -> Option<Token> {
+        let span = self.span.complete();
+        self.errors.push(LexError::UnexpectedChar { c, span });
+
+        None
+    }
+
     /// Creates a 'left parenthesis' token.
     fn mk_left_parenthesis(&mut self) -> Option<Token> {
         debug_assert!(!self.eof_generated);
@@ -298,6 +693,23 @@ impl<'a> Context<'a> {
         Some(t)
     }
 
+    /// Creates a 'dot', 'dot-dot' or 'ellipsis' token. The longest enabled
+    /// match wins, so "..." is an 'ellipsis' when it is enabled.
+    fn mk_dots(&mut self) -> Option<Token> {
+        if self.source.peek() != Some(CHAR_DOT) {
+            self.mk_dot()
+        } else if self.options.ellipsis && self.peek_second() == Some(CHAR_DOT) {
+            self.read_char();
+            self.read_char();
+            self.mk_ellipsis()
+        } else if self.options.ranges {
+            self.read_char();
+            self.mk_dot_dot()
+        } else {
+            self.mk_dot()
+        }
+    }
+
     /// Creates a 'dot' token.
     fn mk_dot(&mut self) -> Option<Token> {
         debug_assert!(!self.eof_generated);
@@ -309,183 +721,241 @@ impl<'a> Context<'a> {
         Some(t)
     }
 
-    /// Creates a 'plus' token.
-    fn mk_plus(&mut self) -> Option<Token> {
+    /// Creates a 'dot-dot' token.
+    fn mk_dot_dot(&mut self) -> Option<Token> {
         debug_assert!(!self.eof_generated);
-        debug_assert!(self.span.is_one_char());
+        debug_assert!(self.span.is_two_chars());
 
         let s = self.span.complete();
-        let t = Token::new_plus(s);
+        let t = Token::new_dot_dot(s);
 
         Some(t)
     }
 
-    /// Creates a 'minus' token.
-    fn mk_minus(&mut self) -> Option<Token> {
+    /// Creates an 'ellipsis' token.
+    fn mk_ellipsis(&mut self) -> Option<Token> {
         debug_assert!(!self.eof_generated);
-        debug_assert!(self.span.is_one_char());
+        debug_assert!(self.span.is_n_chars(3));
 
         let s = self.span.complete();
-        let t = Token::new_minus(s);
+        let t = Token::new_ellipsis(s);
 
         Some(t)
     }
 
-    /// Creates a 'semicolon' token.
-    fn mk_semicolon(&mut self) -> Option<Token> {
+    /// Creates a 'plus' token.
+    fn mk_plus(&mut self) -> Option<Token> {
         debug_assert!(!self.eof_generated);
         debug_assert!(self.span.is_one_char());
 
         let s = self.span.complete();
-        let t = Token::new_semicolon(s);
+        let t = Token::new_plus(s);
 
         Some(t)
     }
 
-    /// Creates a 'star' token.
-    fn mk_star(&mut self) -> Option<Token> {
+    /// Creates a 'minus' token.
+    fn mk_minus(&mut self) -> Option<Token> {
         debug_assert!(!self.eof_generated);
         debug_assert!(self.span.is_one_char());
 
         let s = self.span.complete();
-        let t = Token::new_star(s);
+        let t = Token::new_minus(s);
 
         Some(t)
     }
 
-    /// Creates a 'bang' or 'bang-equal' token.
-    fn mk_bang_or_bang_equal(&mut self) -> Option<Token> {
-        if self.read_char_if(CHAR_EQUAL) {
-            self.mk_bang_equal()
-        } else {
-            self.mk_bang()
-        }
-    }
-
-    /// Creates a 'bang' token.
-    fn mk_bang(&mut self) -> Option<Token> {
+    /// Creates a 'semicolon' token.
+    fn mk_semicolon(&mut self) -> Option<Token> {
         debug_assert!(!self.eof_generated);
         debug_assert!(self.span.is_one_char());
 
         let s = self.span.complete();
-        let t = Token::new_bang(s);
+        let t = Token::new_semicolon(s);
 
         Some(t)
     }
 
-    /// Creates a 'bang-equal' token.
-    fn mk_bang_equal(&mut self) -> Option<Token> {
+    /// Creates a 'star' token.
+    ///
+    /// A "*/" is reported as a stray comment close, since there are no block
+    /// comments and no operand starts with a "/". It still yields a 'star'
+    /// and a 'slash' token. In the regex dialect a "/" after a "*" starts a
+    /// regex, so it is not reported.
+    fn mk_star(&mut self) -> Option<Token> {
         debug_assert!(!self.eof_generated);
-        debug_assert!(self.span.is_two_chars());
-
-        let s = self.span.complete();
-        let t = Token::new_bang_equal(s);
-
-        Some(t)
-    }
+        debug_assert!(self.span.is_one_char());
 
-    /// Creates a 'equal' or 'equal-equal' token.
-    fn mk_equal_or_equal_equal(&mut self) -> Option<Token> {
-        if self.read_char_if(CHAR_EQUAL) {
-            self.mk_equal_equal()
-        } else {
-            self.mk_equal()
+        // A "*//" is a star followed by a line comment, not a stray "*/".
+        if !self.options.regex_literals
+            && self.source.peek() == Some(CHAR_SLASH)
+            && self.peek_second() != Some(CHAR_SLASH)
+        {
+            self.errors.push(LexError::UnexpectedCommentClose {
+                span: self.span.grow(1),
+            });
         }
-    }
-
-    /// Creates a 'equal' token.
-    fn mk_equal(&mut self) -> Option<Token> {
-        debug_assert!(!self.eof_generated);
-        debug_assert!(self.span.is_one_char());
 
         let s = self.span.complete();
-        let t = Token::new_equal(s);
+        let t = Token::new_star(s);
 
         Some(t)
     }
 
-    /// Creates a 'equal-equal' token.
-    fn mk_equal_equal(&mut self) -> Option<Token> {
+    /// Creates an operator token from the table of the operators: the
+    /// two-char operator when the next character completes one, otherwise
+    /// the single-char operator, e.g. "<=" or "<".
+    fn mk_operator(&mut self, first: char) -> Option<Token> {
         debug_assert!(!self.eof_generated);
-        debug_assert!(self.span.is_two_chars());
-
-        let s = self.span.complete();
-        let t = Token::new_equal_equal(s);
-
-        Some(t)
-    }
+        debug_assert!(self.span.is_one_char());
 
-    /// Creates a 'greater' or 'greater-equal' token.
-    fn mk_greater_or_greater_equal(&mut self) -> Option<Token> {
-        if self.read_char_if(CHAR_EQUAL) {
-            self.mk_greater_equal()
-        } else {
-            self.mk_greater()
-        }
-    }
+        let second = self.source.peek();
+        let pair = OPERATOR_PAIRS
+            .iter()
+            .find(|(pair, _)| pair.0 == first && Some(pair.1) == second);
 
-    /// Creates a 'greater' token.
-    fn mk_greater(&mut self) -> Option<Token> {
-        debug_assert!(!self.eof_generated);
-        debug_assert!(self.span.is_one_char());
+        let lexeme = match pair {
+            Some((_, lexeme)) => {
+                self.read_char();
+                lexeme.clone()
+            }
+            None => {
+                let (_, lexeme) = OPERATORS.iter().find(|(c, _)| *c == first)?;
+                lexeme.clone()
+            }
+        };
 
         let s = self.span.complete();
-        let t = Token::new_greater(s);
+        let t = Token::new_fixed(lexeme, s);
 
         Some(t)
     }
 
-    /// Creates a 'greater-equal' token.
-    fn mk_greater_equal(&mut self) -> Option<Token> {
-        debug_assert!(!self.eof_generated);
-        debug_assert!(self.span.is_two_chars());
+    /// Checks if a heredoc starts after the "<" which was just read: a second
+    /// "<" and a tag, which is an identifier at the end of its line.
+    fn is_heredoc_start(&mut self) -> bool {
+        if self.source.peek() != Some(CHAR_LESS)
+            || !self
+                .source
+                .peek_nth(1)
+                .is_some_and(|c| self.is_ident_start(c))
+        {
+            return false;
+        }
 
-        let s = self.span.complete();
-        let t = Token::new_greater_equal(s);
+        let mut n = 2;
+        while self
+            .source
+            .peek_nth(n)
+            .is_some_and(|c| self.is_ident_continue(c))
+        {
+            n += 1;
+        }
 
-        Some(t)
+        self.source.peek_nth(n) == Some(CHAR_NEWLINE)
     }
 
-    /// Creates a 'less' or 'less-equal' token.
-    fn mk_less_or_less_equal(&mut self) -> Option<Token> {
-        if self.read_char_if(CHAR_EQUAL) {
-            self.mk_less_equal()
-        } else {
-            self.mk_less()
+    /// Checks if the next line is the closing line of a heredoc,
+    /// which contains exactly its tag.
+    fn is_heredoc_end(&mut self, tag: &str) -> bool {
+        let mut n = 0;
+        for c in tag.chars() {
+            if self.source.peek_nth(n) != Some(c) {
+                return false;
+            }
+            n += 1;
         }
+
+        matches!(self.source.peek_nth(n), None | Some(CHAR_NEWLINE))
     }
 
-    /// Creates a 'less' token.
-    fn mk_less(&mut self) -> Option<Token> {
+    /// Creates a 'heredoc' token, e.g. "<<END" at the end of a line, the
+    /// lines of the body and a closing line "END". The new line which ends
+    /// the closing line is not part of the token. An unterminated heredoc
+    /// is reported and its token ends at the end of the source.
+    fn mk_heredoc(&mut self) -> Option<Token> {
         debug_assert!(!self.eof_generated);
         debug_assert!(self.span.is_one_char());
 
-        let s = self.span.complete();
-        let t = Token::new_less(s);
+        self.read_char(); // the second "<"
+        let mut tag = String::new();
+        while let Some(c) = self.source.peek().filter(|c| self.is_ident_continue(*c)) {
+            tag.push(c);
+            self.read_char();
+        }
+        self.read_char(); // the new line after the tag
+
+        let terminated = loop {
+            if self.is_heredoc_end(&tag) {
+                tag.chars().for_each(|_| {
+                    self.read_char();
+                });
+                break true;
+            }
 
-        Some(t)
-    }
+            match self.source.peek() {
+                None => break false,
+                Some(_) => self.read_line(),
+            }
+        };
 
-    /// Creates a 'less-equal' token.
-    fn mk_less_equal(&mut self) -> Option<Token> {
-        debug_assert!(!self.eof_generated);
-        debug_assert!(self.span.is_two_chars());
+        if !terminated {
+            self.errors.push(LexError::UnterminatedHeredoc {
+                tag: tag.clone(),
+                span: self.span,
+            });
+        }
+        let body = self.take_payload();
 
         let s = self.span.complete();
-        let t = Token::new_less_equal(s);
+        let t = Token::new(Lexeme::Heredoc { tag, body }, s);
 
         Some(t)
     }
 
-    /// Creates a 'slash' or 'comment' token.
+    /// Creates a 'slash', 'comment' or 'regex' token.
+    ///
+    /// A "//" always starts a comment. With the regex dialect, a single "/"
+    /// is decided by the previous significant token:
+    ///
+    /// | previous token                                            | "/" is   |
+    /// |-----------------------------------------------------------|----------|
+    /// | identifier, number, string, regex, ")", `this`, `super`,  | division |
+    /// | `true`, `false`, `nil`                                    |          |
+    /// | none, "(", ",", "=", operators, other keywords, ";", ... | regex    |
     fn mk_slash_or_comment(&mut self) -> Option<Token> {
         if self.read_char_if(CHAR_SLASH) {
             self.mk_comment()
+        } else if self.options.regex_literals && self.regex_allowed() {
+            self.mk_regex()
         } else {
             self.mk_slash()
         }
     }
 
+    /// Returns true if the previous significant token does not end an operand,
+    /// so a "/" is the start of a regex and not a division.
+    fn regex_allowed(&self) -> bool {
+        use LexemeKind::*;
+
+        !matches!(
+            self.prev_kind,
+            Some(
+                Identifier
+                    | Number
+                    | String
+                    | Regex
+                    | Heredoc
+                    | RightParen
+                    | This
+                    | Super
+                    | True
+                    | False
+                    | Nil
+            )
+        )
+    }
+
     /// Creates a 'slash' token.
     fn mk_slash(&mut self) -> Option<Token> {
         debug_assert!(!self.eof_generated);
@@ -502,10 +972,35 @@ impl<'a> Context<'a> {
         debug_assert!(!self.eof_generated);
         debug_assert!(self.span.is_two_chars());
 
-        let comment = self.read_line();
+        self.read_line();
+        let comment = self.take_payload();
+
+        let s = self.span.complete();
+        let t = Token::new(
+            Lexeme::Comment {
+                text: comment,
+                style: CommentStyle::Line,
+            },
+            s,
+        );
+
+        Some(t)
+    }
+
+    /// Creates a 'regex' token.
+    fn mk_regex(&mut self) -> Option<Token> {
+        debug_assert!(!self.eof_generated);
+        debug_assert!(self.span.is_one_char());
+
+        let terminated = self.read_regex();
+        if !terminated {
+            self.errors
+                .push(LexError::UnterminatedRegex { span: self.span });
+        }
+        let regex = self.take_payload();
 
         let s = self.span.complete();
-        let t = Token::new_comment(&comment, s);
+        let t = Token::new(Lexeme::Regex(regex), s);
 
         Some(t)
     }
@@ -516,7 +1011,11 @@ impl<'a> Context<'a> {
         debug_assert!(self.span.is_multi_line());
 
         let s = self.span.complete();
-        let t = Token::new_newline(s);
+        let mut t = Token::new_newline(s);
+        t.asi = self.options.asi_after.as_ref().map(|kinds| {
+            self.prev_kind
+                .is_some_and(|prev_kind| kinds.contains(&prev_kind))
+        });
 
         Some(t)
     }
@@ -526,23 +1025,56 @@ impl<'a> Context<'a> {
         debug_assert!(!self.eof_generated);
         debug_assert!(self.span.is_one_char());
 
-        let ws = self.read_ws(first_char);
+        self.read_ws(first_char);
+
+        if self.options.lint_mixed_indentation
+            && self.span.start_col.0 == 0
+            && self.buffer.contains(CHAR_TAB)
+            && self.buffer.contains(CHAR_WHITESPACE)
+        {
+            self.errors
+                .push(LexError::MixedIndentation { span: self.span });
+        }
+
+        let ws = self.take_payload();
+
+        let s = self.span.complete();
+        let t = Token::new(Lexeme::Whitespace(ws), s);
+
+        Some(t)
+    }
+
+    /// Creates a 'whitespace' token which may include new lines
+    fn mk_whitespace_and_newlines(&mut self, first_char: char) -> Option<Token> {
+        debug_assert!(!self.eof_generated);
+
+        self.read_ws_and_newlines(first_char);
+        let ws = self.take_payload();
 
         let s = self.span.complete();
-        let t = Token::new_whitespace(&ws, s);
+        let t = Token::new(Lexeme::Whitespace(ws), s);
 
         Some(t)
     }
 
-    /// Creates a 'string' token
-    fn mk_string(&mut self) -> Option<Token> {
+    /// Creates a 'string' token. An unterminated string is reported
+    /// and its token ends at the end of the source.
+    fn mk_string(&mut self, delimiter: char) -> Option<Token> {
         debug_assert!(!self.eof_generated);
         debug_assert!(self.span.is_one_char());
 
-        let string = self.read_string().unwrap();
+        let escapes = self.string_escapes(delimiter).unwrap_or_default();
+        let terminated = self.read_string(delimiter, escapes);
+        if !terminated {
+            self.errors
+                .push(LexError::UnterminatedString { span: self.span });
+        }
+        let string = self.take_payload();
 
         let s = self.span.complete();
-        let t = Token::new_string(&string, s);
+        let mut t = Token::new(Lexeme::String(string), s);
+        t.escapes = Some(escapes);
+        t.closed = Some(terminated);
 
         Some(t)
     }
@@ -553,9 +1085,52 @@ impl<'a> Context<'a> {
         debug_assert!(self.span.is_one_char());
 
         let number = self.read_number(first_digit).unwrap();
+        let digits = self.number_digits();
+        let overflow = self
+            .options
+            .integer_overflow
+            .filter(|_| digits.chars().all(is_digit) && i64::from_str(&digits).is_err());
+
+        let mut malformed = !self.has_valid_separators();
+
+        let next = self.source.peek();
+        if self.options.strict_numbers && next.is_some_and(|c| self.is_ident_start(c)) {
+            while let Some(c) = self.source.peek().filter(|c| self.is_ident_continue(*c)) {
+                self.buffer.push(c);
+                self.read_char();
+            }
+
+            malformed = true;
+        }
+
+        if malformed {
+            self.errors
+                .push(LexError::MalformedNumber { span: self.span });
+        }
+
+        let mut digits = self.buffer.chars();
+        let leading_zero = digits.next() == Some(CHAR_0) && digits.next().is_some_and(is_digit);
+        let raw = self.take_payload();
 
         let s = self.span.complete();
-        let t = Token::new_number(number, s);
+
+        if leading_zero {
+            self.warnings.push(LexWarning::LeadingZero { span: s });
+        }
+        match overflow {
+            Some(OverflowMode::ToFloat) => {
+                self.warnings.push(LexWarning::PrecisionLoss { span: s })
+            }
+            Some(OverflowMode::Error) => self.errors.push(LexError::IntegerOverflow { span: s }),
+            None => (),
+        }
+        let t = Token::new(
+            Lexeme::Number(NumberLit {
+                value: number,
+                raw: raw.into_boxed_str(),
+            }),
+            s,
+        );
 
         Some(t)
     }
@@ -565,25 +1140,99 @@ impl<'a> Context<'a> {
         debug_assert!(!self.eof_generated);
         debug_assert!(self.span.is_one_char());
 
-        let i = self.read_identifier(first_char);
+        // A "r#" is a raw prefix only in front of an identifier, otherwise
+        // the "r" is an identifier and the "#" an unexpected character.
+        if first_char == CHAR_RAW_PREFIX
+            && self.source.peek() == Some(CHAR_HASH)
+            && self.peek_second().is_some_and(|c| self.is_ident_start(c))
+        {
+            self.read_char();
+            return self.mk_raw_identifier();
+        }
+
+        self.read_identifier(first_char);
 
         let s = self.span.complete();
 
-        let srch = KEYWORDS.binary_search_by_key(&&*i, |&(k, _)| k);
+        // The keywords are lowercase, so the identifier is lowercased to be
+        // searched in a case-insensitive dialect, keeping the buffer as is.
+        let key = if self.options.case_insensitive_keywords {
+            Cow::Owned(self.buffer.to_ascii_lowercase())
+        } else {
+            Cow::Borrowed(self.buffer.as_str())
+        };
+        let srch = KEYWORDS.binary_search_by_key(&key.as_ref(), |&(k, _)| k);
+
+        // A soft keyword is downgraded to an identifier, keeping its text.
+        let soft = |kw: &str| {
+            self.options
+                .soft_keywords
+                .as_ref()
+                .is_some_and(|soft| soft.iter().any(|s| s == kw))
+        };
 
         let token = match srch {
-            Ok(index) => Token::new(KEYWORDS[index].1.clone(), s),
-            Err(_) => Token::new_identifier(&i, s),
+            Ok(index) if !soft(KEYWORDS[index].0) => Token::new(KEYWORDS[index].1.clone(), s),
+            _ => Token::new(self.identifier_lexeme(), s),
         };
 
         Some(token)
     }
 
-    /// Creates a new 'eof' token while updating the context
-    fn mk_eof_token(&mut self) -> Option<Token> {
+    /// Creates a 'identifier' token for a raw identifier, e.g. "r#while",
+    /// whose name is never matched against the keywords. The "r#" prefix
+    /// was already read and it is not part of the name, which follows it.
+    fn mk_raw_identifier(&mut self) -> Option<Token> {
         debug_assert!(!self.eof_generated);
+        debug_assert!(self.span.is_two_chars());
+
+        let first = self
+            .read_char()
+            .expect("a raw prefix is followed by an identifier");
+        self.read_identifier(first);
+
+        let s = self.span.complete();
+        let t = Token::new(self.identifier_lexeme(), s);
+
+        Some(t)
+    }
+
+    /// Returns the lexeme of the identifier in the buffer, which
+    /// is interned as a symbol when the lexer has an interner.
+    fn identifier_lexeme(&mut self) -> Lexeme {
+        match self.hooks.interner.as_mut() {
+            Some(interner) => Lexeme::Symbol(interner.intern(&self.buffer)),
+            None => Lexeme::Identifier(self.take_payload()),
+        }
+    }
+
+    /// Creates a new 'bof' token while updating the context
+    fn mk_bof_token(&mut self) -> Option<Token> {
+        debug_assert!(!self.bof_generated);
+
+        self.bof_generated = true;
+        let s = self.span.complete();
+        let t = Token::new_bof(s);
+
+        Some(t)
+    }
+
+    /// Creates a new 'eof' token while updating the context. The span of the
+    /// 'eof' token starts and ends at the final position reached: right after
+    /// the last character, or at the start of the next line if the source
+    /// ends with a new line.
+    fn mk_eof_token(&mut self) -> Option<Token> {
+        debug_assert!(!self.eof_generated, "the eof token is emitted only once");
 
         self.eof_generated = true; // mark that we reaced the end of stream
+        self.check_line_len(); // the last line has no new line
+        if self.options.eof_on_last_line && self.span.end_col.0 == 0 && self.span.end_line.0 > 1 {
+            // move back to the end of the last line, before the final new line
+            self.span.end_line = Line(self.span.end_line.0 - 1);
+            self.span.end_col = self.last_line_end;
+        }
+        self.span.start_line = self.span.end_line; // start at the final position
+        self.span.start_col = self.span.end_col;
         let s = self.span.complete(); // complete the span
         let t = Token::new_eof(s); // create a new token
 
@@ -595,17 +1244,402 @@ impl<'a> Context<'a> {
 // Lexer iterator
 //
 
+/// The tokens, the errors and the warnings resulted from lexing a whole source.
+/// See [LexerIter::lex_all].
+pub struct LexOutput {
+    /// The tokens, ending with the 'eof' token
+    pub tokens: Vec<Token>,
+    /// The errors reported while lexing
+    pub errors: Vec<LexError>,
+    /// The non-fatal warnings reported while lexing
+    pub warnings: Vec<LexWarning>,
+    /// The regions skipped to recover from the errors, see [LexerIter::recover_to_statement]
+    pub recovered: Vec<Span>,
+}
+
 /// An iterator for collection of tokens generated
 /// during the parsing of a source string. See [Lexer] for more details.
 pub struct LexerIter<'a> {
-    ctx: Context<'a>,
+    pub(crate) ctx: Context<'a>,
+    progress: Option<Progress<'a>>,
+    pub(crate) pushback: Vec<Token>,
+    errors_returned: usize, // the number of errors returned by try_next
+}
+
+/// The progress callback and the number of tokens read so far.
+struct Progress<'a> {
+    callback: Box<dyn FnMut(usize, usize) + 'a>,
+    tokens: usize,
+}
+
+impl<'a> LexerIter<'a> {
+    pub(crate) fn new(ctx: Context<'a>) -> Self {
+        Self {
+            ctx,
+            progress: None,
+            pushback: Vec::new(),
+            errors_returned: 0,
+        }
+    }
+
+    /// Restarts the lexer at the start of its source, keeping its options, so
+    /// the same tokens are read again and their ids restart at 0. The errors,
+    /// the warnings and the tokens pushed back are dropped. Returns `false`,
+    /// without doing anything, for a lexer which was not created from a string.
+    pub fn reset(&mut self) -> bool {
+        let text = match self.ctx.text {
+            Some(text) => text,
+            None => return false,
+        };
+
+        let mut ctx = Context::new(text);
+        ctx.span = self.ctx.origin;
+        ctx.origin = self.ctx.origin;
+        ctx.options = std::mem::take(&mut self.ctx.options);
+        ctx.hooks = std::mem::take(&mut self.ctx.hooks);
+
+        self.ctx = ctx;
+        self.pushback.clear();
+        self.errors_returned = 0;
+        if let Some(progress) = self.progress.as_mut() {
+            progress.tokens = 0;
+        }
+
+        true
+    }
+
+    /// Pushes a token back, so the next call of `next()` returns it.
+    /// The tokens pushed back more times without an intervening `next()`
+    /// are returned in the reverse order, the last one first.
+    pub fn rewind_one(&mut self, token: Token) {
+        self.pushback.push(token);
+    }
+
+    /// Returns the next token without consuming it. It shares the buffer
+    /// of [LexerIter::rewind_one], so it returns the last token pushed back.
+    pub fn peek(&mut self) -> Option<&Token> {
+        if self.pushback.is_empty() {
+            let tkn = self.next()?;
+            self.pushback.push(tkn);
+        }
+
+        self.pushback.last()
+    }
+
+    /// Returns the next token, or the next error in the order it was reported,
+    /// e.g. a [LexError::UnexpectedChar] for a `@`, so the caller handles the
+    /// errors as they come instead of collecting them at the end. The lexing
+    /// continues past an error, and the next call returns the next token.
+    pub fn try_next(&mut self) -> Option<Result<Token, LexError>> {
+        if let Some(err) = self.next_error() {
+            return Some(Err(err));
+        }
+
+        let tkn = self.next();
+        match self.next_error() {
+            Some(err) => {
+                // The error comes before the token, which is returned next.
+                self.pushback.extend(tkn);
+                Some(Err(err))
+            }
+            None => tkn.map(Ok),
+        }
+    }
+
+    /// Returns the first error not returned yet by [LexerIter::try_next].
+    fn next_error(&mut self) -> Option<LexError> {
+        let err = self.ctx.errors.get(self.errors_returned)?.clone();
+        self.errors_returned += 1;
+        Some(err)
+    }
+
+    /// Calls `callback(bytes_done, bytes_total)` every 1024 tokens and once
+    /// more after the 'eof' token, when `bytes_done == bytes_total`, e.g. to
+    /// update a progress bar while lexing a huge source. For a source given
+    /// by [Lexer::from_char_iter] the total is not known and it is 0.
+    pub fn on_progress(mut self, callback: impl FnMut(usize, usize) + 'a) -> Self {
+        self.progress = Some(Progress {
+            callback: Box::new(callback),
+            tokens: 0,
+        });
+        self
+    }
+
+    /// Emits a single 'whitespace' token for a run of whitespaces
+    /// and new lines, instead of separate 'whitespace' and 'new line' tokens.
+    pub fn merge_whitespace_and_newlines(mut self) -> Self {
+        self.ctx.options.merge_whitespace_and_newlines = true;
+        self
+    }
+
+    /// Emits a zero-width 'bof' token at the start of the source, before any
+    /// other token, so the leading trivia has a token to attach to.
+    pub fn emit_bof(mut self) -> Self {
+        self.ctx.options.emit_bof = true;
+        self
+    }
+
+    /// Reads a "," flanked by digits as the decimal separator of a number,
+    /// e.g. "1,5" is the number 1.5. Any other "," is a 'comma' token.
+    ///
+    /// Inside parentheses, a "," is always a 'comma' token, so "f(1,2)" is
+    /// a call with the two arguments 1 and 2. A decimal number is then
+    /// written with a dot, e.g. "f(1.5)".
+    pub fn decimal_comma(mut self) -> Self {
+        self.ctx.options.decimal_comma = true;
+        self
+    }
+
+    /// Reads the underscores between the digits of a number as separators,
+    /// e.g. "1_000" is the number 1000 and its raw text keeps "1_000".
+    /// An underscore which is not between two digits, e.g. in "1__0" or
+    /// "1_", is reported as a [LexError::MalformedNumber].
+    pub fn digit_separators(mut self) -> Self {
+        self.ctx.options.digit_separators = true;
+        self
+    }
+
+    /// Reports a [LexError::MalformedNumber] for a number immediately followed
+    /// by an identifier, e.g. "123abc", which becomes a single 'number' token
+    /// with the raw text "123abc". By default it is a number and an identifier.
+    pub fn strict_numbers(mut self) -> Self {
+        self.ctx.options.strict_numbers = true;
+        self
+    }
+
+    /// Reads the exponent of a number, e.g. "1e3", "1.e3" or "2.5E-3". An
+    /// "e" which is not followed by digits stays an identifier, e.g. "1else".
+    /// Without it, "1e3" is the number 1 and the identifier "e3".
+    pub fn exponents(mut self) -> Self {
+        self.ctx.options.exponents = true;
+        self
+    }
+
+    /// Reads ".." as a 'dot-dot' token, e.g. for the ranges "1..5".
+    /// Without it, ".." is two 'dot' tokens.
+    pub fn ranges(mut self) -> Self {
+        self.ctx.options.ranges = true;
+        self
+    }
+
+    /// Reports a [LexError::MixedIndentation] for each whitespace at the
+    /// start of a line which contains both tabs and spaces.
+    pub fn lint_mixed_indentation(mut self) -> Self {
+        self.ctx.options.lint_mixed_indentation = true;
+        self
+    }
+
+    /// Sets the delimiters of the strings, instead of the double quote, each
+    /// with the escape policy of its strings. A string is terminated by the
+    /// same delimiter which opened it, e.g. with a backtick "`text`" is a 'string'.
+    pub fn string_delimiters(mut self, delimiters: &[(char, EscapePolicy)]) -> Self {
+        self.ctx.options.string_delimiters = Some(delimiters.to_vec());
+        self
+    }
+
+    /// Sets how the columns of the spans are counted. By default each character
+    /// advances the column by 1. With [PositionEncoding::Utf16] the columns
+    /// match the LSP positions, e.g. an emoji advances the column by 2.
+    pub fn position_encoding(mut self, encoding: PositionEncoding) -> Self {
+        self.ctx.options.position_encoding = encoding;
+        self
+    }
+
+    /// Sets how the escape sequences of the strings are handled. By default
+    /// they are kept [EscapePolicy::Raw]. Lox has no char literals, so
+    /// only the strings are affected.
+    pub fn escape_policy(mut self, policy: EscapePolicy) -> Self {
+        self.ctx.options.escape_policy = policy;
+        self
+    }
+
+    /// Reports a [LexError::LineTooLong] for each line longer than
+    /// `limit` characters, at the first character past the limit.
+    pub fn lint_line_length(mut self, limit: usize) -> Self {
+        self.ctx.options.lint_line_len = Some(limit);
+        self
+    }
+
+    /// Reads a "/" in expression position as the start of a regex literal,
+    /// e.g. "x = /ab+/;", as in the JavaScript-flavored dialects. A "/" which
+    /// follows an operand, e.g. "x / y", is still a division.
+    pub fn regex_literals(mut self) -> Self {
+        self.ctx.options.regex_literals = true;
+        self
+    }
+
+    /// Reads "..." as an 'ellipsis' token, e.g. for the variadic functions
+    /// and the spread arguments. Without it, "..." is a 'dot-dot' and a 'dot'
+    /// with the [LexerIter::ranges], and three 'dot' tokens otherwise.
+    pub fn ellipsis(mut self) -> Self {
+        self.ctx.options.ellipsis = true;
+        self
+    }
+
+    /// Tags each 'new line' token with whether it permits an automatic semicolon
+    /// insertion, which is when the previous significant token is of one of
+    /// the given kinds, e.g. `return`, `)` or an identifier. The tag is
+    /// returned by [Token::permits_asi].
+    pub fn asi_after(mut self, kinds: &[LexemeKind]) -> Self {
+        self.ctx.options.asi_after = Some(kinds.to_vec());
+        self
+    }
+
+    /// After an error, skips the tokens until the next statement boundary,
+    /// a ';', a '}' or a new line, so a parser does not see the cascading
+    /// errors. Only the first error is reported, and the skipped region is
+    /// returned in [LexOutput::recovered]. The lint errors do not trigger it.
+    pub fn recover_to_statement(mut self) -> Self {
+        self.ctx.options.recover_to_statement = true;
+        self
+    }
+
+    /// Sets the id of the source, which is carried by all the tokens, e.g. to
+    /// know which file a token comes from when the token streams of several
+    /// sources are merged. The default id is 0.
+    pub fn source_id(mut self, id: u32) -> Self {
+        self.ctx.options.source_id = id;
+        self
+    }
+
+    /// Checks the integer literals which do not fit in an `i64`, e.g.
+    /// `99999999999999999999`. They are still 'number' tokens, read as
+    /// floats, and they are reported as a precision loss warning or as an
+    /// error, depending on the mode.
+    pub fn integer_overflow(mut self, mode: OverflowMode) -> Self {
+        self.ctx.options.integer_overflow = Some(mode);
+        self
+    }
+
+    /// Matches the keywords regardless of their case, e.g. `VAR`, `Var` and
+    /// `var` are all the 'var' keyword. The identifiers keep their casing.
+    pub fn case_insensitive_keywords(mut self) -> Self {
+        self.ctx.options.case_insensitive_keywords = true;
+        self
+    }
+
+    /// Reads the given keywords as identifiers, e.g. `print` to use it as the
+    /// name of a field, while the other keywords stay reserved. A name which
+    /// is not a keyword has no effect.
+    pub fn soft_keywords(mut self, keywords: &[&str]) -> Self {
+        self.ctx.options.soft_keywords = Some(keywords.iter().map(|k| k.to_string()).collect());
+        self
+    }
+
+    /// Emits the 'eof' token on the last line of the source, at its end, so
+    /// its line is the number of lines, e.g. for tools which show "N lines".
+    /// By default, a source which ends with a new line has its 'eof' token
+    /// at the start of the next, empty, line.
+    ///
+    /// Note that this option breaks the ordering of the spans: the 'eof' token
+    /// then starts where the final 'new line' token starts, and it ends before
+    /// it, so it sorts before it by the [Ord] of [Span]. The starts of the spans
+    /// are still in order, so a binary search should be done by the start.
+    pub fn eof_on_last_line(mut self) -> Self {
+        self.ctx.options.eof_on_last_line = true;
+        self
+    }
+
+    /// Reads a "\r\n" as one 'new line' token, so a whitespace token stops
+    /// before the "\r" and the line ending is not split. By default, the
+    /// "\r" is a whitespace and only the "\n" is a new line. Note that
+    /// [crate::reconstruct] gives back a "\n" for any 'new line' token.
+    pub fn crlf_newlines(mut self) -> Self {
+        self.ctx.options.crlf_newlines = true;
+        self
+    }
+
+    /// Reads the heredocs, e.g. a "<<END" at the end of a line, followed by
+    /// the lines of the body up to a line which contains exactly "END", as
+    /// a single 'heredoc' token. Otherwise a "<<" is two 'less' tokens.
+    pub fn heredocs(mut self) -> Self {
+        self.ctx.options.heredocs = true;
+        self
+    }
+
+    /// Lexes the whole source, collecting the tokens, the errors and the warnings.
+    /// The errors and the warnings are sorted in source order, by the start of their span.
+    pub fn lex_all(mut self) -> LexOutput {
+        let tokens: Vec<Token> = self.by_ref().collect();
+        let mut errors = std::mem::take(&mut self.ctx.errors);
+        errors.sort_by_key(|e| {
+            let span = e.span();
+            (span.start_line.0, span.start_col.0)
+        });
+
+        let mut warnings = std::mem::take(&mut self.ctx.warnings);
+        warnings.sort_by_key(|w| {
+            let span = w.span();
+            (span.start_line.0, span.start_col.0)
+        });
+
+        LexOutput {
+            tokens,
+            errors,
+            warnings,
+            recovered: std::mem::take(&mut self.ctx.recovered),
+        }
+    }
+
+    /// Counts the tokens of the source, including the 'eof' token. The
+    /// text payloads are not built, as with [Lexer::scan_kinds], which
+    /// makes it cheaper than collecting the tokens of a large source.
+    pub fn token_count(mut self) -> usize {
+        self.ctx.options.buffered_payloads = true;
+        self.count()
+    }
+
+    /// Returns the length, in characters, of the longest operator the lexer
+    /// reads with its options, e.g. to know how much lookahead a re-lexer
+    /// needs. It is 2 for the two-char operators, such as "==" and "..",
+    /// and 3 when the [LexerIter::ellipsis] is read.
+    pub fn max_operator_len(&self) -> usize {
+        if self.ctx.options.ellipsis {
+            3
+        } else {
+            2
+        }
+    }
+
+    /// Lexes the whole source and returns the `(line, length)` of each line
+    /// longer than `max` characters. The length is counted in characters
+    /// and does not include the new line.
+    pub fn long_lines(mut self, max: usize) -> Vec<(usize, usize)> {
+        self.ctx.options.max_line_len = Some(max);
+        self.by_ref().for_each(drop);
+
+        std::mem::take(&mut self.ctx.long_lines)
+    }
+
+    /// Counts the occurrences of each keyword in the source.
+    pub fn keyword_histogram(self) -> HashMap<Lexeme, usize> {
+        let mut histogram = HashMap::new();
+
+        self.filter(|tkn| tkn.lexeme.canonical_keyword_text().is_some())
+            .for_each(|tkn| *histogram.entry(tkn.lexeme).or_insert(0) += 1);
+
+        histogram
+    }
 }
 
 impl<'a> Iterator for LexerIter<'a> {
     type Item = Token;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.ctx.read_token()
+        if let Some(tkn) = self.pushback.pop() {
+            return Some(tkn);
+        }
+
+        let tkn = self.ctx.read_token();
+
+        if let (Some(progress), Some(t)) = (self.progress.as_mut(), &tkn) {
+            progress.tokens += 1;
+            if progress.tokens % PROGRESS_INTERVAL == 0 || t.lexeme == Lexeme::Eof {
+                (progress.callback)(self.ctx.bytes_read, self.ctx.bytes_total);
+            }
+        }
+
+        tkn
     }
 }
 
@@ -623,23 +1657,134 @@ impl<'a> Iterator for LexerIter<'a> {
 /// let source = "var language=\n\"lox\";";
 /// Lexer::with_source(source).for_each(|c| println!("{:?}", c));
 /// ```
-
 pub struct Lexer {}
 
 impl Lexer {
     /// Returns an iterator which containts the
     /// tokens resulted from parsing the source string.
     #[inline]
-    pub fn with_source(source: &str) -> LexerIter {
+    pub fn with_source(source: &str) -> LexerIter<'_> {
+        Lexer::iter(source)
+    }
+
+    /// Returns an iterator over the kinds of the tokens resulted from parsing
+    /// the source string. The text payloads are not allocated, which makes
+    /// it a cheap way to classify a source.
+    pub fn scan_kinds(source: &str) -> impl Iterator<Item = LexemeKind> + '_ {
+        let mut lxr = Lexer::iter(source);
+        lxr.ctx.options.buffered_payloads = true;
+
+        lxr.map(|tkn| tkn.lexeme.kind())
+    }
+
+    /// Lexes a source only for its errors, in source order, e.g. for a check
+    /// which does not need the tokens. It is the check-only counterpart of
+    /// [LexerIter::lex_all]: the tokens are dropped and their text payloads
+    /// are not built.
+    pub fn validate(source: &str) -> Vec<LexError> {
+        let mut lxr = Lexer::iter(source);
+        lxr.ctx.options.buffered_payloads = true;
+
+        lxr.lex_all().errors
+    }
+
+    /// Returns an iterator which containts the tokens resulted from parsing
+    /// the characters of an iterator, e.g. decoded on the fly from a custom
+    /// encoding. The spans track the lines and the columns of the characters.
+    pub fn from_char_iter<'a>(chars: impl Iterator<Item = char> + 'a) -> LexerIter<'a> {
+        LexerIter::new(Context::from_chars(chars))
+    }
+
+    /// Returns the span of the last token before the 'eof' token, which tells
+    /// where the content of the source ended, or `None` for an empty source.
+    /// A source which ends with a new line has a 'new line' last token.
+    pub fn last_token_span(source: &str) -> Option<Span> {
         Lexer::iter(source)
+            .take_while(|tkn| tkn.lexeme != Lexeme::Eof)
+            .last()
+            .map(|tkn| tkn.span)
+    }
+
+    /// Re-lexes only the text of a span of the source, e.g. to check that an
+    /// edited token still has the same kind without lexing the whole source.
+    /// The spans of the returned tokens are positioned in the whole source
+    /// and the tokens do not include an 'eof' token.
+    ///
+    /// The span should start at a token boundary, otherwise the tokens are
+    /// not meaningful. Returns a [LexError::InvalidSpan] if the span is
+    /// outside of the source, or the first error reported while lexing.
+    pub fn relex_span(source: &str, span: Span) -> Result<Vec<Token>, LexError> {
+        let range = SourceMap::new(source)
+            .byte_range(span)
+            .filter(|range| range.start <= range.end)
+            .ok_or(LexError::InvalidSpan { span })?;
+
+        let lxr = Lexer::iter_at(&source[range], span.start_line, span.start_col);
+
+        let mut output = lxr.lex_all();
+        output.tokens.pop(); // the 'eof' token
+
+        match output.errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(output.tokens),
+        }
+    }
+
+    /// Returns an iterator which containts the tokens resulted from parsing
+    /// the byte range of a larger source, without copying it. The spans are
+    /// offset by the `(line, column)` where the range starts in the source.
+    /// It returns `None` when the range is out of the source or when it
+    /// does not fall on character boundaries.
+    pub fn with_source_range(
+        source: &str,
+        range: Range<usize>,
+        start: (Line, Column),
+    ) -> Option<LexerIter<'_>> {
+        let source = source.get(range)?;
+        Some(Lexer::iter_at(source, start.0, start.1))
+    }
+
+    /// Returns an iterator over the tokens of a source which starts at
+    /// a given line and column, e.g. because it is a part of a larger source.
+    pub(crate) fn iter_at(source: &str, line: Line, col: Column) -> LexerIter<'_> {
+        let mut lxr = Lexer::iter(source);
+        lxr.ctx.span.start_line = line;
+        lxr.ctx.span.start_col = col;
+        lxr.ctx.span.end_line = line;
+        lxr.ctx.span.end_col = col;
+        lxr.ctx.origin = lxr.ctx.span;
+        lxr
+    }
+
+    /// Returns an iterator which containts the tokens resulted from parsing
+    /// a source given as bytes, e.g. a memory-mapped file. The bytes are
+    /// validated as UTF-8 up front.
+    pub fn from_bytes(source: &[u8]) -> Result<LexerIter<'_>, Utf8Error> {
+        let source = std::str::from_utf8(source)?;
+        Ok(Lexer::iter(source))
+    }
+
+    /// Returns an iterator which containts the tokens resulted from parsing
+    /// a source given as bytes, which may not be valid UTF-8. Each invalid
+    /// sequence is replaced by U+FFFD, which is reported as a
+    /// [LexError::UnexpectedChar] and skipped outside of a string. The bytes
+    /// are decoded lazily.
+    pub fn from_bytes_lossy(source: &[u8]) -> LexerIter<'_> {
+        let chars = source.utf8_chunks().flat_map(|chunk| {
+            let invalid = (!chunk.invalid().is_empty()).then_some(char::REPLACEMENT_CHARACTER);
+            chunk.valid().chars().chain(invalid)
+        });
+
+        let mut ctx = Context::from_chars(chars);
+        ctx.bytes_total = source.len();
+
+        LexerIter::new(ctx)
     }
 
     /// Returns an iterator which containts the
     /// tokens resulted from parsing the source string.
-    fn iter(source: &str) -> LexerIter {
-        LexerIter {
-            ctx: Context::new(source),
-        }
+    fn iter(source: &str) -> LexerIter<'_> {
+        LexerIter::new(Context::new(source))
     }
 }
 
@@ -651,7 +1796,7 @@ impl<'a> From<&'a str> for LexerIter<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::Lexeme;
+    use crate::{Column, Lexeme, Line};
 
     use super::*;
 
@@ -809,6 +1954,123 @@ mod tests {
         read_and_ignore(&mut ctx);
     }
 
+    #[test]
+    fn test_read_dot_dot() {
+        let source = format!("_{}{}_", CHAR_DOT, CHAR_DOT);
+        let mut ctx = Context::new(source.as_str());
+        ctx.options.ranges = true;
+
+        read_and_ignore(&mut ctx);
+
+        let c = ctx.read_char().unwrap();
+        assert_eq!(CHAR_DOT, c);
+
+        let tkn = ctx.read_token_with_char(c).unwrap();
+
+        assert_eq!(1, tkn.span.start_col.0);
+        assert!(tkn.span.is_two_chars());
+        assert_eq!(Lexeme::DotDot, tkn.lexeme);
+
+        // Read the _ character
+        read_and_ignore(&mut ctx);
+    }
+
+    #[test]
+    fn test_numbers_and_dots() {
+        let lex = |source| {
+            Lexer::with_source(source)
+                .exponents()
+                .ranges()
+                .map(|tkn| tkn.lexeme)
+                .filter(|l| *l != Lexeme::Eof)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec![Lexeme::Number(NumberLit::new(1e3, "1.e3"))],
+            lex("1.e3")
+        );
+        assert_eq!(
+            vec![
+                Lexeme::Number(NumberLit::new(1., "1")),
+                Lexeme::Dot,
+                Lexeme::Identifier("foo".to_string()),
+            ],
+            lex("1.foo")
+        );
+        assert_eq!(
+            vec![
+                Lexeme::Number(NumberLit::new(1., "1")),
+                Lexeme::DotDot,
+                Lexeme::Number(NumberLit::new(5., "5")),
+            ],
+            lex("1..5")
+        );
+        assert_eq!(vec![Lexeme::Number(NumberLit::new(1e3, "1e3"))], lex("1e3"));
+    }
+
+    #[test]
+    fn test_numbers_and_dots_off() {
+        let lex = |source| {
+            Lexer::with_source(source)
+                .map(|tkn| tkn.lexeme)
+                .filter(|l| *l != Lexeme::Eof)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec![
+                Lexeme::Number(NumberLit::new(1., "1")),
+                Lexeme::Dot,
+                Lexeme::Identifier("e3".to_string()),
+            ],
+            lex("1.e3")
+        );
+        assert_eq!(
+            vec![
+                Lexeme::Number(NumberLit::new(1., "1")),
+                Lexeme::Dot,
+                Lexeme::Identifier("foo".to_string()),
+            ],
+            lex("1.foo")
+        );
+        assert_eq!(
+            vec![
+                Lexeme::Number(NumberLit::new(1., "1")),
+                Lexeme::Dot,
+                Lexeme::Dot,
+                Lexeme::Number(NumberLit::new(5., "5")),
+            ],
+            lex("1..5")
+        );
+        assert_eq!(
+            vec![
+                Lexeme::Number(NumberLit::new(1., "1")),
+                Lexeme::Identifier("e3".to_string()),
+            ],
+            lex("1e3")
+        );
+    }
+
+    #[test]
+    fn test_numbers_with_exponent() {
+        let lex = |source| {
+            Lexer::with_source(source)
+                .exponents()
+                .next()
+                .unwrap()
+                .lexeme
+        };
+
+        assert_eq!(
+            Lexeme::Number(NumberLit::new(2.5e-3, "2.5E-3")),
+            lex("2.5E-3")
+        );
+        assert_eq!(Lexeme::Number(NumberLit::new(1e3, "1e+3")), lex("1e+3"));
+        assert_eq!(Lexeme::Number(NumberLit::new(1., "1")), lex("1else"));
+        assert_eq!(Lexeme::Number(NumberLit::new(1., "1")), lex("1e-x"));
+    }
+
     #[test]
     fn test_read_plus() {
         let source = format!("_{}_", CHAR_PLUS);
@@ -1082,8 +2344,7 @@ mod tests {
         let tkn = ctx.read_token_with_char(c).unwrap();
 
         assert_eq!(1, tkn.span.start_col.0);
-        let cmnt = "_".to_string();
-        assert_eq!(Lexeme::Comment(cmnt), tkn.lexeme);
+        assert_eq!(Lexeme::line_comment("_"), tkn.lexeme);
 
         // Read the _ character
         let c = ctx.read_char();
@@ -1170,12 +2431,25 @@ mod tests {
 
         assert_eq!(1, tkn.span.start_col.0);
         assert!(tkn.span.is_one_line());
-        assert_eq!(Lexeme::Number(12.3), tkn.lexeme);
+        assert_eq!(Lexeme::Number(NumberLit::new(12.3, "12.3")), tkn.lexeme);
 
         // Read the _ character
         read_and_ignore(&mut ctx);
     }
 
+    #[test]
+    fn test_number_value_and_raw() {
+        let tkn = Lexer::with_source("12.3").next().unwrap();
+
+        match tkn.lexeme {
+            Lexeme::Number(number) => {
+                assert_eq!(12.3, number.value);
+                assert_eq!("12.3", &*number.raw);
+            }
+            l => panic!("unexpected lexeme {:?}", l),
+        }
+    }
+
     #[test]
     fn test_read_and() {
         let source = ".and.";
@@ -1516,6 +2790,1384 @@ mod tests {
         read_and_ignore(&mut ctx);
     }
 
+    #[test]
+    fn test_display_number_matches_source() {
+        for source in &["10", "10.0", "1.50"] {
+            let tkn = Lexer::with_source(source).next().unwrap();
+            assert_eq!(format!("num({})", source), format!("{}", tkn.lexeme));
+        }
+
+        for source in &["1e3", "2.5E-3"] {
+            let tkn = Lexer::with_source(source).exponents().next().unwrap();
+            assert_eq!(format!("num({})", source), format!("{}", tkn.lexeme));
+        }
+    }
+
+    #[test]
+    fn test_merge_whitespace_and_newlines() {
+        let source = "var   \n\tx";
+        let tkns: Vec<Token> = Lexer::with_source(source)
+            .merge_whitespace_and_newlines()
+            .collect();
+
+        assert_eq!(4, tkns.len());
+        assert_eq!(Lexeme::Var, tkns[0].lexeme);
+        assert_eq!(Lexeme::Whitespace("   \n\t".to_string()), tkns[1].lexeme);
+        assert!(tkns[1].span.is_multi_line());
+        assert_eq!(Lexeme::Identifier("x".to_string()), tkns[2].lexeme);
+        assert_eq!(Lexeme::Eof, tkns[3].lexeme);
+    }
+
+    #[test]
+    fn test_whitespace_and_newlines_not_merged_by_default() {
+        let source = "var   \n\tx";
+        let tkns: Vec<Token> = Lexer::with_source(source).collect();
+
+        assert_eq!(Lexeme::Whitespace("   ".to_string()), tkns[1].lexeme);
+        assert_eq!(Lexeme::NewLine, tkns[2].lexeme);
+        assert_eq!(Lexeme::Whitespace("\t".to_string()), tkns[3].lexeme);
+    }
+
+    #[test]
+    fn test_long_lines() {
+        let source = "var x;\nvar long_name = \"a long string\";\nvar y;";
+        let long_lines = Lexer::with_source(source).long_lines(20);
+
+        assert_eq!(vec![(2, 32)], long_lines);
+    }
+
+    #[test]
+    fn test_long_lines_last_line_and_multi_byte() {
+        let source = "// \u{e9}\u{e9}\u{e9}\n\"abc\ndef\" // \u{e9}\u{e9}\u{e9}";
+        let long_lines = Lexer::with_source(source).long_lines(6);
+
+        assert_eq!(vec![(3, 11)], long_lines);
+    }
+
+    #[test]
+    fn test_keyword_histogram() {
+        let source = "var x = 1;\nvar y = 2;\nif (x) print y;";
+        let histogram = Lexer::with_source(source).keyword_histogram();
+
+        assert_eq!(3, histogram.len());
+        assert_eq!(Some(&2), histogram.get(&Lexeme::Var));
+        assert_eq!(Some(&1), histogram.get(&Lexeme::If));
+        assert_eq!(Some(&1), histogram.get(&Lexeme::Print));
+        assert_eq!(None, histogram.get(&Lexeme::While));
+    }
+
+    #[test]
+    fn test_scan_kinds() {
+        let source = "var x = \"lox\"; // comment";
+        let expected: Vec<LexemeKind> = Lexer::with_source(source)
+            .map(|tkn| tkn.lexeme.kind())
+            .collect();
+        let kinds: Vec<LexemeKind> = Lexer::scan_kinds(source).collect();
+
+        assert_eq!(expected, kinds);
+        assert_eq!(LexemeKind::Var, kinds[0]);
+        assert_eq!(LexemeKind::String, kinds[6]);
+        assert_eq!(LexemeKind::Comment, kinds[9]);
+    }
+
+    fn kinds(tkns: &[Token]) -> Vec<LexemeKind> {
+        tkns.iter().map(|tkn| tkn.lexeme.kind()).collect()
+    }
+
+    #[test]
+    fn test_decimal_comma() {
+        let tkns: Vec<Token> = Lexer::with_source("1,5").decimal_comma().collect();
+
+        assert_eq!(2, tkns.len());
+        assert_eq!(Lexeme::Number(NumberLit::new(1.5, "1,5")), tkns[0].lexeme);
+        assert!(tkns[0].span.is_n_chars(3));
+    }
+
+    #[test]
+    fn test_decimal_comma_off_keeps_commas() {
+        let tkns: Vec<Token> = Lexer::with_source("f(1,2)").collect();
+
+        assert_eq!(
+            vec![
+                LexemeKind::Identifier,
+                LexemeKind::LeftParen,
+                LexemeKind::Number,
+                LexemeKind::Comma,
+                LexemeKind::Number,
+                LexemeKind::RightParen,
+                LexemeKind::Eof
+            ],
+            kinds(&tkns)
+        );
+    }
+
+    #[test]
+    fn test_decimal_comma_keeps_argument_commas() {
+        let expected = vec![
+            LexemeKind::Identifier,
+            LexemeKind::LeftParen,
+            LexemeKind::Number,
+            LexemeKind::Comma,
+            LexemeKind::Whitespace,
+            LexemeKind::Number,
+            LexemeKind::RightParen,
+            LexemeKind::Eof,
+        ];
+
+        let tkns: Vec<Token> = Lexer::with_source("f(1, 2)").decimal_comma().collect();
+        assert_eq!(expected, kinds(&tkns));
+        assert_eq!(Lexeme::Number(NumberLit::new(1., "1")), tkns[2].lexeme);
+
+        let tkns: Vec<Token> = Lexer::with_source("f(a, 2)").decimal_comma().collect();
+        assert_eq!(LexemeKind::Identifier, tkns[2].lexeme.kind());
+        assert_eq!(LexemeKind::Comma, tkns[3].lexeme.kind());
+    }
+
+    #[test]
+    fn test_decimal_comma_trailing_comma() {
+        let tkns: Vec<Token> = Lexer::with_source("f(1,)").decimal_comma().collect();
+
+        assert_eq!(LexemeKind::Number, tkns[2].lexeme.kind());
+        assert_eq!(LexemeKind::Comma, tkns[3].lexeme.kind());
+        assert_eq!(LexemeKind::RightParen, tkns[4].lexeme.kind());
+    }
+
+    #[test]
+    fn test_decimal_comma_flanked_by_digits() {
+        let tkns: Vec<Token> = Lexer::with_source("f(1,2)").decimal_comma().collect();
+
+        assert_eq!(
+            vec![
+                LexemeKind::Identifier,
+                LexemeKind::LeftParen,
+                LexemeKind::Number,
+                LexemeKind::Comma,
+                LexemeKind::Number,
+                LexemeKind::RightParen,
+                LexemeKind::Eof
+            ],
+            kinds(&tkns)
+        );
+        assert_eq!(Lexeme::Number(NumberLit::new(1., "1")), tkns[2].lexeme);
+        assert_eq!(Lexeme::Number(NumberLit::new(2., "2")), tkns[4].lexeme);
+
+        let tkns: Vec<Token> = Lexer::with_source("f(g(1),2) + 3,5")
+            .decimal_comma()
+            .collect();
+        assert_eq!(Lexeme::Number(NumberLit::new(2., "2")), tkns[7].lexeme);
+        assert_eq!(
+            Lexeme::Number(NumberLit::new(3.5, "3,5")),
+            tkns[tkns.len() - 2].lexeme
+        );
+    }
+
+    #[test]
+    fn test_lint_mixed_indentation() {
+        let source = "{\n\t  code\n}";
+        let out = Lexer::with_source(source)
+            .lint_mixed_indentation()
+            .lex_all();
+
+        assert_eq!(1, out.errors.len());
+        let mut span = Span::new(Line(2), Column(0));
+        span.incr_col_n(2);
+        assert_eq!(LexError::MixedIndentation { span }, out.errors[0]);
+    }
+
+    #[test]
+    fn test_lint_mixed_indentation_single_style() {
+        let source = "{\n\t\tcode\n    code\n}";
+        let out = Lexer::with_source(source)
+            .lint_mixed_indentation()
+            .lex_all();
+        assert!(out.errors.is_empty());
+    }
+
+    #[test]
+    fn test_lint_mixed_indentation_not_leading() {
+        let source = "code \t code";
+        let out = Lexer::with_source(source)
+            .lint_mixed_indentation()
+            .lex_all();
+        assert!(out.errors.is_empty());
+    }
+
+    #[test]
+    fn test_lint_mixed_indentation_off_by_default() {
+        let source = "{\n\t  code\n}";
+        let out = Lexer::with_source(source).lex_all();
+
+        assert!(out.errors.is_empty());
+        assert_eq!(Lexeme::Whitespace("\t  ".to_string()), out.tokens[2].lexeme);
+    }
+
+    #[test]
+    fn test_escape_policy_raw() {
+        let tkns: Vec<Token> = Lexer::with_source(r#""a\nb""#)
+            .escape_policy(EscapePolicy::Raw)
+            .collect();
+
+        assert_eq!(Lexeme::String(r"a\nb".to_string()), tkns[0].lexeme);
+        assert_eq!(Some(EscapePolicy::Raw), tkns[0].escape_policy());
+        assert_eq!(None, tkns[1].escape_policy());
+    }
+
+    #[test]
+    fn test_escape_policy_decoded() {
+        let tkns: Vec<Token> = Lexer::with_source(r#""a\nb""#)
+            .escape_policy(EscapePolicy::Decoded)
+            .collect();
+
+        assert_eq!(Lexeme::String("a\nb".to_string()), tkns[0].lexeme);
+        assert_eq!(Some(EscapePolicy::Decoded), tkns[0].escape_policy());
+        assert!(tkns[0].span.is_one_line());
+    }
+
+    #[test]
+    fn test_escape_policy_decoded_sequences() {
+        let tkns: Vec<Token> = Lexer::with_source(r#""\t\"\\\u{e9}\q\u{zz}""#)
+            .escape_policy(EscapePolicy::Decoded)
+            .collect();
+
+        assert_eq!(
+            Lexeme::String("\t\"\\\u{e9}\\q\\u{zz}".to_string()),
+            tkns[0].lexeme
+        );
+    }
+
+    #[test]
+    fn test_lint_line_length() {
+        let source = format!("{}\nprint x;\n{}", "a".repeat(100), "b".repeat(81));
+        let output = Lexer::with_source(&source).lint_line_length(80).lex_all();
+
+        assert_eq!(
+            vec![
+                LexError::LineTooLong {
+                    line: 1,
+                    limit: 80,
+                    span: Span::new(Line(1), Column(80)),
+                },
+                LexError::LineTooLong {
+                    line: 3,
+                    limit: 80,
+                    span: Span::new(Line(3), Column(80)),
+                },
+            ],
+            output.errors
+        );
+        assert!(output.errors.iter().all(|err| err.span().is_one_char()));
+    }
+
+    #[test]
+    fn test_lint_line_length_wide_char() {
+        let source = format!("{}\"\u{1d11e}\"", "a".repeat(78));
+        let output = Lexer::with_source(&source)
+            .position_encoding(PositionEncoding::Utf16)
+            .lint_line_length(80)
+            .lex_all();
+
+        let span = output.errors[0].span();
+        assert_eq!(1, output.errors.len());
+        assert_eq!(79, span.start_col.0);
+        assert!(span.is_n_chars(2));
+    }
+
+    #[test]
+    fn test_lint_line_length_off() {
+        let source = "a".repeat(100);
+        let output = Lexer::with_source(&source).lex_all();
+
+        assert!(output.errors.is_empty());
+    }
+
+    #[test]
+    fn test_ellipsis() {
+        let lex = |source| {
+            Lexer::with_source(source)
+                .ellipsis()
+                .map(|tkn| tkn.lexeme)
+                .filter(|l| *l != Lexeme::Eof)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(vec![Lexeme::Dot], lex("."));
+        assert_eq!(vec![Lexeme::Dot, Lexeme::Dot], lex(".."));
+        assert_eq!(vec![Lexeme::Ellipsis], lex("..."));
+        assert_eq!(vec![Lexeme::Ellipsis, Lexeme::Dot], lex("...."));
+        assert_eq!(
+            vec![
+                Lexeme::Number(NumberLit::new(1., "1")),
+                Lexeme::Ellipsis,
+                Lexeme::Number(NumberLit::new(5., "5")),
+            ],
+            lex("1...5")
+        );
+    }
+
+    #[test]
+    fn test_asi_after() {
+        let kinds = [
+            LexemeKind::Return,
+            LexemeKind::RightParen,
+            LexemeKind::Identifier,
+            LexemeKind::Number,
+        ];
+        let newlines: Vec<Option<bool>> = Lexer::with_source("return\nx +\n1\n")
+            .asi_after(&kinds)
+            .filter(|tkn| tkn.lexeme == Lexeme::NewLine)
+            .map(|tkn| tkn.permits_asi())
+            .collect();
+
+        assert_eq!(vec![Some(true), Some(false), Some(true)], newlines);
+    }
+
+    #[test]
+    fn test_asi_after_off() {
+        let tkns: Vec<Token> = Lexer::with_source("return\n").collect();
+
+        assert_eq!(None, tkns[1].permits_asi());
+    }
+
+    #[test]
+    fn test_recover_to_statement() {
+        let output = Lexer::with_source("var x = 1 12ab 2 3x; print x;")
+            .strict_numbers()
+            .recover_to_statement()
+            .lex_all();
+
+        assert_eq!(1, output.errors.len());
+        assert!(matches!(output.errors[0], LexError::MalformedNumber { .. }));
+        assert_eq!(10, output.errors[0].span().start_col.0);
+
+        assert_eq!(1, output.recovered.len());
+        let skipped = output.recovered[0];
+        assert_eq!((10, 19), (skipped.start_col.0, skipped.end_col.0));
+
+        let lexemes: Vec<Lexeme> = output.tokens.into_iter().map(|tkn| tkn.lexeme).collect();
+        assert_eq!(Lexeme::Number(NumberLit::new(1., "1")), lexemes[6]);
+        assert_eq!(Lexeme::Whitespace(" ".to_string()), lexemes[7]);
+        assert_eq!(Lexeme::Semicolon, lexemes[8]);
+        assert_eq!(Lexeme::Print, lexemes[10]);
+    }
+
+    #[test]
+    fn test_recover_to_statement_off() {
+        let output = Lexer::with_source("var x = 1 12ab 2 3x;")
+            .strict_numbers()
+            .lex_all();
+
+        assert_eq!(2, output.errors.len());
+        assert!(output.recovered.is_empty());
+    }
+
+    #[test]
+    fn test_source_id() {
+        let tkns: Vec<Token> = Lexer::with_source("var x;").source_id(7).collect();
+        assert!(tkns.iter().all(|tkn| tkn.source_id() == 7));
+
+        let tkns: Vec<Token> = Lexer::with_source("var x;").collect();
+        assert!(tkns.iter().all(|tkn| tkn.source_id() == 0));
+    }
+
+    #[test]
+    fn test_integer_overflow_to_float() {
+        let output = Lexer::with_source("99999999999999999999 9223372036854775807")
+            .integer_overflow(OverflowMode::ToFloat)
+            .lex_all();
+
+        assert_eq!(
+            Lexeme::Number(NumberLit::new(1e20, "99999999999999999999")),
+            output.tokens[0].lexeme
+        );
+        assert!(output.errors.is_empty());
+        assert_eq!(
+            vec![LexWarning::PrecisionLoss {
+                span: output.tokens[0].span
+            }],
+            output.warnings
+        );
+    }
+
+    #[test]
+    fn test_integer_overflow_error() {
+        let output = Lexer::with_source("99999999999999999999 1.5e30")
+            .integer_overflow(OverflowMode::Error)
+            .lex_all();
+
+        assert!(output.warnings.is_empty());
+        assert_eq!(
+            vec![LexError::IntegerOverflow {
+                span: output.tokens[0].span
+            }],
+            output.errors
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive_keywords() {
+        let tkns: Vec<Token> = Lexer::with_source("VAR Var Foo r#VAR")
+            .case_insensitive_keywords()
+            .collect();
+
+        assert_eq!(Lexeme::Var, tkns[0].lexeme);
+        assert_eq!(Lexeme::Var, tkns[2].lexeme);
+        assert_eq!(Lexeme::Identifier("Foo".to_string()), tkns[4].lexeme);
+        assert_eq!(Lexeme::Identifier("VAR".to_string()), tkns[6].lexeme);
+    }
+
+    #[test]
+    fn test_case_insensitive_keywords_off() {
+        let tkns: Vec<Token> = Lexer::with_source("VAR").collect();
+
+        assert_eq!(Lexeme::Identifier("VAR".to_string()), tkns[0].lexeme);
+    }
+
+    #[test]
+    fn test_soft_keywords() {
+        let tkns: Vec<Token> = Lexer::with_source("print x; var p = PRINT;")
+            .soft_keywords(&["print"])
+            .case_insensitive_keywords()
+            .collect();
+
+        assert_eq!(Lexeme::Identifier("print".to_string()), tkns[0].lexeme);
+        assert_eq!(Lexeme::Identifier("x".to_string()), tkns[2].lexeme);
+        assert_eq!(Lexeme::Var, tkns[5].lexeme);
+        assert_eq!(Lexeme::Identifier("PRINT".to_string()), tkns[11].lexeme);
+
+        let tkns: Vec<Token> = Lexer::with_source("print x").collect();
+        assert_eq!(Lexeme::Print, tkns[0].lexeme);
+    }
+
+    #[test]
+    fn test_string_quote_spans() {
+        let tkns: Vec<Token> = Lexer::with_source("x = \"ab\ncd\";").collect();
+        let tkn = &tkns[4];
+
+        assert_eq!(Lexeme::String("ab\ncd".to_string()), tkn.lexeme);
+        assert_eq!(Some(Span::new(Line(1), Column(4))), tkn.open_quote_span());
+        assert_eq!(Some(Span::new(Line(2), Column(2))), tkn.close_quote_span());
+        assert_eq!(None, tkns[0].open_quote_span());
+    }
+
+    #[test]
+    fn test_string_quote_spans_unterminated() {
+        let tkns: Vec<Token> = Lexer::with_source("\"ab").collect();
+
+        assert_eq!(
+            Some(Span::new(Line(1), Column(0))),
+            tkns[0].open_quote_span()
+        );
+        assert_eq!(None, tkns[0].close_quote_span());
+    }
+
+    #[test]
+    fn test_string_unterminated_at_eof() {
+        let output = Lexer::with_source("x = \"hello").lex_all();
+
+        assert_eq!(6, output.tokens.len());
+        let string = &output.tokens[4];
+        assert_eq!(Lexeme::String("hello".to_string()), string.lexeme);
+        assert_eq!(Span::new(Line(1), Column(4)).grow(5), string.span);
+        assert_eq!(Lexeme::Eof, output.tokens[5].lexeme);
+        assert_eq!(
+            vec![LexError::UnterminatedString { span: string.span }],
+            output.errors
+        );
+    }
+
+    #[test]
+    fn test_digit_separators() {
+        let tkns: Vec<Token> = Lexer::with_source("1_000 2_5.0_1e1_0")
+            .digit_separators()
+            .exponents()
+            .collect();
+
+        assert_eq!(
+            Lexeme::Number(NumberLit::new(1000., "1_000")),
+            tkns[0].lexeme
+        );
+        assert_eq!(
+            Lexeme::Number(NumberLit::new(25.01e10, "2_5.0_1e1_0")),
+            tkns[2].lexeme
+        );
+        assert!(tkns[0].span.is_n_chars(5));
+    }
+
+    #[test]
+    fn test_digit_separators_malformed() {
+        let output = Lexer::with_source("1__0 2_").digit_separators().lex_all();
+        let tkns = &output.tokens;
+
+        assert_eq!(Lexeme::Number(NumberLit::new(10., "1__0")), tkns[0].lexeme);
+        assert_eq!(Lexeme::Number(NumberLit::new(2., "2_")), tkns[2].lexeme);
+        assert_eq!(
+            vec![
+                LexError::MalformedNumber { span: tkns[0].span },
+                LexError::MalformedNumber { span: tkns[2].span }
+            ],
+            output.errors
+        );
+    }
+
+    #[test]
+    fn test_digit_separators_off() {
+        let tkns: Vec<Token> = Lexer::with_source("1_000").collect();
+
+        assert_eq!(Lexeme::Number(NumberLit::new(1., "1")), tkns[0].lexeme);
+        assert_eq!(Lexeme::Identifier("_000".to_string()), tkns[1].lexeme);
+    }
+
+    #[test]
+    fn test_token_count() {
+        let source = "var x = \"lox\"; // comment\nprint x + 1.5;";
+        let tokens: Vec<Token> = Lexer::with_source(source).collect();
+
+        assert_eq!(tokens.len(), Lexer::with_source(source).token_count());
+        assert_eq!(1, Lexer::with_source("").token_count());
+    }
+
+    #[test]
+    fn test_operator_pairs() {
+        for ((first, second), lexeme) in OPERATOR_PAIRS {
+            let source = format!("{}{}", first, second);
+            let tkns: Vec<Token> = Lexer::with_source(&source).collect();
+
+            assert_eq!(2, tkns.len());
+            assert_eq!(*lexeme, tkns[0].lexeme);
+            assert!(tkns[0].span.is_two_chars());
+        }
+
+        for (c, lexeme) in OPERATORS {
+            let source = format!("{} {}", c, c);
+            let tkns: Vec<Token> = Lexer::with_source(&source).collect();
+
+            assert_eq!(*lexeme, tkns[0].lexeme);
+            assert_eq!(*lexeme, tkns[2].lexeme);
+            assert!(tkns[0].span.is_one_char());
+        }
+    }
+
+    #[test]
+    fn test_eof_on_last_line() {
+        let source = "var a;\nvar b;\n\nprint a;\nprint b;\n";
+        let tkns: Vec<Token> = Lexer::with_source(source).eof_on_last_line().collect();
+        let eof = tkns[tkns.len() - 1].span;
+
+        assert!(eof.is_eof());
+        assert_eq!(5, eof.start_line.0);
+        assert_eq!(5, eof.end_line.0);
+        assert_eq!(8, eof.start_col.0);
+
+        let tkns: Vec<Token> = Lexer::with_source(source).collect();
+        assert_eq!(6, tkns[tkns.len() - 1].span.start_line.0);
+    }
+
+    #[test]
+    fn test_eof_on_last_line_span_order() {
+        let source = "var a;\nprint a;\n";
+        let tkns: Vec<Token> = Lexer::with_source(source).eof_on_last_line().collect();
+        let newline = tkns[tkns.len() - 2].span;
+        let eof = tkns[tkns.len() - 1].span;
+
+        // The 'eof' token sorts before the final 'new line' token.
+        assert!(eof < newline);
+        assert_eq!(newline.caret_position(), eof.caret_position());
+
+        // The starts of the spans are still in order.
+        let starts: Vec<_> = tkns.iter().map(|t| t.span.caret_position()).collect();
+        assert!(starts.windows(2).all(|w| w[0] <= w[1]));
+
+        let tkns: Vec<Token> = Lexer::with_source(source).collect();
+        assert!(tkns.windows(2).all(|w| w[0].span <= w[1].span));
+    }
+
+    #[test]
+    fn test_eof_on_last_line_without_final_newline() {
+        let tkns: Vec<Token> = Lexer::with_source("var a;\nprint a;")
+            .eof_on_last_line()
+            .collect();
+        let eof = tkns[tkns.len() - 1].span;
+
+        assert_eq!((2, 8), eof.caret_position());
+
+        let tkns: Vec<Token> = Lexer::with_source("").eof_on_last_line().collect();
+        assert_eq!((1, 0), tkns[0].span.caret_position());
+    }
+
+    #[test]
+    fn test_whitespace_before_crlf() {
+        let tkns: Vec<Token> = Lexer::with_source("  \r\n").collect();
+
+        assert_eq!(3, tkns.len());
+        assert_eq!(Lexeme::Whitespace("  \r".to_string()), tkns[0].lexeme);
+        assert_eq!(Lexeme::NewLine, tkns[1].lexeme);
+        assert_eq!(Lexeme::Eof, tkns[2].lexeme);
+    }
+
+    #[test]
+    fn test_crlf_newlines() {
+        let tkns: Vec<Token> = Lexer::with_source("  \r\n").crlf_newlines().collect();
+
+        assert_eq!(3, tkns.len());
+        assert_eq!(Lexeme::Whitespace("  ".to_string()), tkns[0].lexeme);
+        assert_eq!(Lexeme::NewLine, tkns[1].lexeme);
+        assert_eq!((1, 2), tkns[1].span.caret_position());
+        assert!(tkns[1].span.is_multi_line());
+        assert_eq!((2, 0), tkns[2].span.caret_position());
+
+        let tkns: Vec<Token> = Lexer::with_source("a\r\r\nb").crlf_newlines().collect();
+        assert_eq!(Lexeme::Whitespace("\r".to_string()), tkns[1].lexeme);
+        assert_eq!(Lexeme::NewLine, tkns[2].lexeme);
+        assert_eq!(Lexeme::Identifier("b".to_string()), tkns[3].lexeme);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Lexer::validate("var x = \"lox\"; // ok\n").is_empty());
+
+        let source = "var x = @;\nprint \"open";
+        assert_eq!(
+            Lexer::with_source(source).lex_all().errors,
+            Lexer::validate(source)
+        );
+        assert_eq!(
+            vec![
+                LexError::UnexpectedChar {
+                    c: '@',
+                    span: Span::new(Line(1), Column(8))
+                },
+                LexError::UnterminatedString {
+                    span: Span::new(Line(2), Column(6)).grow(4)
+                },
+            ],
+            Lexer::validate(source)
+        );
+    }
+
+    #[test]
+    fn test_heredoc() {
+        let source = "x = <<END\na: 1\n  END\nEND\ny";
+        let output = Lexer::with_source(source).heredocs().lex_all();
+        let tkns = &output.tokens;
+
+        assert!(output.errors.is_empty());
+        assert_eq!(
+            Lexeme::Heredoc {
+                tag: "END".to_string(),
+                body: "a: 1\n  END\n".to_string()
+            },
+            tkns[4].lexeme
+        );
+        assert_eq!((1, 4), tkns[4].span.caret_position());
+        assert_eq!(4, tkns[4].span.end_line.0);
+        assert_eq!(3, tkns[4].span.end_col.0);
+        assert_eq!(Lexeme::NewLine, tkns[5].lexeme);
+        assert_eq!(Lexeme::Identifier("y".to_string()), tkns[6].lexeme);
+        assert_eq!(source, crate::reconstruct(tkns));
+    }
+
+    #[test]
+    fn test_heredoc_unterminated() {
+        let output = Lexer::with_source("<<EOT\nabc\nEOTX").heredocs().lex_all();
+
+        assert_eq!(
+            Lexeme::Heredoc {
+                tag: "EOT".to_string(),
+                body: "abc\nEOTX".to_string()
+            },
+            output.tokens[0].lexeme
+        );
+        assert_eq!(Lexeme::Eof, output.tokens[1].lexeme);
+        assert_eq!(
+            vec![LexError::UnterminatedHeredoc {
+                tag: "EOT".to_string(),
+                span: output.tokens[0].span
+            }],
+            output.errors
+        );
+    }
+
+    #[test]
+    fn test_heredoc_off() {
+        let kinds: Vec<LexemeKind> = Lexer::scan_kinds("<<END\nEND").collect();
+        assert_eq!(LexemeKind::Less, kinds[0]);
+        assert_eq!(LexemeKind::Less, kinds[1]);
+
+        // Not at the end of the line, it is not a heredoc.
+        let tkns: Vec<Token> = Lexer::with_source("a <<b;").heredocs().collect();
+        assert_eq!(Lexeme::Less, tkns[2].lexeme);
+        assert_eq!(Lexeme::Less, tkns[3].lexeme);
+    }
+
+    #[test]
+    fn test_stray_comment_close() {
+        let output = Lexer::with_source("a */ b").lex_all();
+        let tkns = &output.tokens;
+
+        assert_eq!(Lexeme::Star, tkns[2].lexeme);
+        assert_eq!(Lexeme::Slash, tkns[3].lexeme);
+        assert_eq!(
+            vec![LexError::UnexpectedCommentClose {
+                span: Span::new(Line(1), Column(2)).grow(1)
+            }],
+            output.errors
+        );
+
+        assert!(Lexer::validate("a * b / c").is_empty());
+        assert!(Lexer::validate("x = a *// times two\n 2;").is_empty());
+        let tkns: Vec<Token> = Lexer::with_source("a *// c").collect();
+        assert_eq!(Lexeme::Star, tkns[2].lexeme);
+        assert_eq!(Lexeme::line_comment(" c"), tkns[3].lexeme);
+        assert!(Lexer::with_source("a * /b/")
+            .regex_literals()
+            .lex_all()
+            .errors
+            .is_empty());
+    }
+
+    #[test]
+    fn test_token_ids() {
+        let mut lxr = Lexer::with_source("var x = 1;");
+        let tkns: Vec<Token> = lxr.by_ref().collect();
+
+        assert_eq!(9, tkns.len());
+        for (i, tkn) in tkns.iter().enumerate() {
+            assert_eq!(i as u32, tkn.id());
+        }
+
+        assert!(lxr.reset());
+        let again: Vec<Token> = lxr.collect();
+        assert_eq!(tkns, again);
+        assert_eq!(0, again[0].id());
+        assert_eq!(8, again[8].id());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut lxr = Lexer::with_source_range("ab\n  \"x\" y", 5..10, (Line(2), Column(2)))
+            .unwrap()
+            .source_id(3);
+        let first: Vec<Token> = lxr.by_ref().collect();
+
+        assert!(lxr.reset());
+        let second: Vec<Token> = lxr.collect();
+        assert_eq!(first, second);
+        assert_eq!((2, 2), second[0].span.caret_position());
+        assert_eq!(3, second[0].source_id());
+
+        let mut lxr = Lexer::from_char_iter("a b".chars());
+        assert!(!lxr.reset());
+    }
+
+    #[test]
+    fn test_max_operator_len() {
+        assert_eq!(2, Lexer::with_source("").max_operator_len());
+        assert_eq!(3, Lexer::with_source("").ellipsis().max_operator_len());
+    }
+
+    #[test]
+    fn test_try_next() {
+        let mut lxr = Lexer::with_source("a @ b#\n`");
+        let mut items = Vec::new();
+        while let Some(item) = lxr.try_next() {
+            items.push(item.map(|t| t.lexeme));
+        }
+
+        let at = Span::new(Line(1), Column(2));
+        assert_eq!(Err(LexError::UnexpectedChar { c: '@', span: at }), items[2]);
+        assert_eq!(Ok(Lexeme::Identifier("b".to_string())), items[4]);
+
+        let hash = Span::new(Line(1), Column(5));
+        assert_eq!(
+            Err(LexError::UnexpectedChar { c: '#', span: hash }),
+            items[5]
+        );
+        assert_eq!(Ok(Lexeme::NewLine), items[6]);
+
+        let tick = Span::new(Line(2), Column(0));
+        assert_eq!(
+            Err(LexError::UnexpectedChar { c: '`', span: tick }),
+            items[7]
+        );
+        assert_eq!(Ok(Lexeme::Eof), items[8]);
+        assert_eq!(9, items.len());
+    }
+
+    #[test]
+    fn test_ellipsis_off() {
+        let tkns: Vec<Token> = Lexer::with_source("...").collect();
+
+        assert_eq!(Lexeme::Dot, tkns[0].lexeme);
+        assert_eq!(Lexeme::Dot, tkns[1].lexeme);
+        assert_eq!(Lexeme::Dot, tkns[2].lexeme);
+
+        let tkns: Vec<Token> = Lexer::with_source("...").ranges().collect();
+
+        assert_eq!(Lexeme::DotDot, tkns[0].lexeme);
+        assert_eq!(Lexeme::Dot, tkns[1].lexeme);
+
+        let tkns: Vec<Token> = Lexer::with_source("....").ranges().ellipsis().collect();
+
+        assert_eq!(Lexeme::Ellipsis, tkns[0].lexeme);
+        assert_eq!(Lexeme::Dot, tkns[1].lexeme);
+    }
+
+    #[test]
+    fn test_regex_literals_division() {
+        let tkns: Vec<Token> = Lexer::with_source("x / y").regex_literals().collect();
+
+        assert_eq!(Lexeme::Slash, tkns[2].lexeme);
+        assert_eq!(Lexeme::Identifier("y".to_string()), tkns[4].lexeme);
+    }
+
+    #[test]
+    fn test_regex_literals_regex() {
+        let tkns: Vec<Token> = Lexer::with_source("x = /ab+/;").regex_literals().collect();
+
+        assert_eq!(Lexeme::Regex("ab+".to_string()), tkns[4].lexeme);
+        assert!(tkns[4].span.is_n_chars(5));
+        assert_eq!(Lexeme::Semicolon, tkns[5].lexeme);
+    }
+
+    #[test]
+    fn test_regex_literals_after_paren() {
+        let tkns: Vec<Token> = Lexer::with_source("f(/a\\/b/) / 2")
+            .regex_literals()
+            .collect();
+
+        assert_eq!(Lexeme::Regex("a\\/b".to_string()), tkns[2].lexeme);
+        assert_eq!(Lexeme::Slash, tkns[5].lexeme);
+    }
+
+    #[test]
+    fn test_regex_literals_unterminated() {
+        let output = Lexer::with_source("x = /a").regex_literals().lex_all();
+        let regex = &output.tokens[4];
+
+        assert_eq!(Lexeme::Regex("a".to_string()), regex.lexeme);
+        assert_eq!(Span::new(Line(1), Column(4)).grow(1), regex.span);
+        assert_eq!(Lexeme::Eof, output.tokens[5].lexeme);
+        assert_eq!(6, output.tokens.len());
+        assert_eq!(
+            vec![LexError::UnterminatedRegex { span: regex.span }],
+            output.errors
+        );
+
+        let output = Lexer::with_source("x = /a\ny").regex_literals().lex_all();
+        assert_eq!(Lexeme::Regex("a".to_string()), output.tokens[4].lexeme);
+        assert_eq!(Lexeme::NewLine, output.tokens[5].lexeme);
+        assert_eq!(1, output.errors.len());
+    }
+
+    #[test]
+    fn test_regex_literals_off() {
+        let tkns: Vec<Token> = Lexer::with_source("x = /a").collect();
+
+        assert_eq!(Lexeme::Slash, tkns[4].lexeme);
+    }
+
+    #[test]
+    fn test_raw_identifier_keyword() {
+        let tkns: Vec<Token> = Lexer::with_source("r#while").collect();
+
+        assert_eq!(Lexeme::Identifier("while".to_string()), tkns[0].lexeme);
+        assert!(tkns[0].span.is_n_chars(7));
+        assert_eq!(Lexeme::Eof, tkns[1].lexeme);
+    }
+
+    #[test]
+    fn test_raw_identifier() {
+        let tkns: Vec<Token> = Lexer::with_source("r#foo r").collect();
+
+        assert_eq!(Lexeme::Identifier("foo".to_string()), tkns[0].lexeme);
+        assert_eq!(Lexeme::Identifier("r".to_string()), tkns[2].lexeme);
+    }
+
+    #[test]
+    fn test_raw_identifier_prefix_only_in_front() {
+        let tkns: Vec<Token> = Lexer::with_source("rfoo").collect();
+
+        assert_eq!(Lexeme::Identifier("rfoo".to_string()), tkns[0].lexeme);
+    }
+
+    #[test]
+    fn test_raw_identifier_empty() {
+        let output = Lexer::with_source("r# x").lex_all();
+
+        assert_eq!(Lexeme::Identifier("r".to_string()), output.tokens[0].lexeme);
+        assert!(output.tokens[0].span.is_one_char());
+        assert_eq!(Lexeme::Whitespace(" ".to_string()), output.tokens[1].lexeme);
+        assert_eq!(
+            vec![LexError::UnexpectedChar {
+                c: '#',
+                span: Span::new(Line(1), Column(1))
+            }],
+            output.errors
+        );
+
+        let tkns: Vec<Token> = Lexer::with_source("r#").collect();
+        assert_eq!(Lexeme::Identifier("r".to_string()), tkns[0].lexeme);
+        assert_eq!(Lexeme::Eof, tkns[1].lexeme);
+    }
+
+    #[test]
+    fn test_relex_span() {
+        let source = "var x = 1;\nprint \"h\u{e9}llo\";";
+        let tkns: Vec<Token> = Lexer::with_source(source).collect();
+
+        for tkn in tkns.iter().filter(|tkn| tkn.lexeme != Lexeme::Eof) {
+            let relexed = Lexer::relex_span(source, tkn.span).unwrap();
+
+            assert_eq!(1, relexed.len());
+            assert_eq!(tkn.lexeme, relexed[0].lexeme);
+            assert_eq!(tkn.span, relexed[0].span);
+        }
+    }
+
+    #[test]
+    fn test_with_source_range() {
+        let source = "// header\nvar x = \"h\u{e9}\";\nprint x;\n// footer";
+        let start = source.find("var").unwrap();
+        let end = source.find("// footer").unwrap();
+
+        let tkns: Vec<Token> = Lexer::with_source_range(source, start..end, (Line(2), Column(0)))
+            .unwrap()
+            .collect();
+        let expected: Vec<Token> = Lexer::with_source(source).skip(1).take(14).collect();
+
+        assert_eq!(expected, tkns[..14]);
+        assert_eq!(Lexeme::Eof, tkns[14].lexeme);
+        assert_eq!(
+            (4, 0),
+            (tkns[14].span.start_line.0, tkns[14].span.start_col.0)
+        );
+    }
+
+    #[test]
+    fn test_with_source_range_invalid() {
+        let source = "\"h\u{e9}\"";
+        let start = (Line(1), Column(0));
+
+        assert!(Lexer::with_source_range(source, 0..3, start).is_none());
+        assert!(Lexer::with_source_range(source, 0..10, start).is_none());
+        assert!(Lexer::with_source_range(source, 0..4, start).is_some());
+    }
+
+    #[test]
+    fn test_relex_span_edited() {
+        let source = "var x = 1;\nprint xy;";
+        let mut span = Lexer::with_source("var x = 1;\nprint x;")
+            .nth(11)
+            .unwrap()
+            .span;
+        span.end_col = Column(8);
+
+        let relexed = Lexer::relex_span(source, span).unwrap();
+        assert_eq!(1, relexed.len());
+        assert_eq!(Lexeme::Identifier("xy".to_string()), relexed[0].lexeme);
+        assert_eq!(2, relexed[0].span.start_line.0);
+        assert_eq!(6, relexed[0].span.start_col.0);
+    }
+
+    #[test]
+    fn test_relex_span_invalid() {
+        let span = Span::new(Line(3), Column(0));
+        let error = Lexer::relex_span("var x;", span).unwrap_err();
+
+        assert_eq!(LexError::InvalidSpan { span }, error);
+    }
+
+    #[test]
+    fn test_lex_all_errors_in_source_order() {
+        let source = format!("x = /a\n\t {}\nx = /b", " ".repeat(10));
+        let output = Lexer::with_source(&source)
+            .regex_literals()
+            .lint_mixed_indentation()
+            .lint_line_length(8)
+            .lex_all();
+
+        let starts: Vec<(usize, usize)> = output
+            .errors
+            .iter()
+            .map(|e| (e.span().start_line.0, e.span().start_col.0))
+            .collect();
+        assert_eq!(vec![(1, 4), (2, 0), (2, 8), (3, 4)], starts);
+        assert!(matches!(
+            output.errors[1],
+            LexError::MixedIndentation { .. }
+        ));
+        assert!(matches!(output.errors[2], LexError::LineTooLong { .. }));
+    }
+
+    #[test]
+    fn test_emit_bof() {
+        let tkns: Vec<Token> = Lexer::with_source("// license\nvar x;")
+            .emit_bof()
+            .collect();
+
+        assert_eq!(Lexeme::Bof, tkns[0].lexeme);
+        assert!(tkns[0].span.is_eof());
+        assert_eq!(1, tkns[0].span.start_line.0);
+        assert_eq!(0, tkns[0].span.start_col.0);
+        assert_eq!(Lexeme::line_comment(" license\n"), tkns[1].lexeme);
+        assert_eq!(Lexeme::Eof, tkns[tkns.len() - 1].lexeme);
+    }
+
+    #[test]
+    fn test_emit_bof_empty_source() {
+        let tkns: Vec<Token> = Lexer::with_source("").emit_bof().collect();
+
+        assert_eq!(2, tkns.len());
+        assert_eq!(Lexeme::Bof, tkns[0].lexeme);
+        assert_eq!(Lexeme::Eof, tkns[1].lexeme);
+    }
+
+    #[test]
+    fn test_no_bof_by_default() {
+        let tkns: Vec<Token> = Lexer::with_source("var x;").collect();
+
+        assert!(tkns.iter().all(|tkn| tkn.lexeme != Lexeme::Bof));
+    }
+
+    #[test]
+    fn test_leading_zero_warning() {
+        let output = Lexer::with_source("x = 0123;").lex_all();
+
+        assert_eq!(
+            Lexeme::Number(NumberLit::new(123., "0123")),
+            output.tokens[4].lexeme
+        );
+        assert!(output.errors.is_empty());
+        assert_eq!(
+            vec![LexWarning::LeadingZero {
+                span: output.tokens[4].span
+            }],
+            output.warnings
+        );
+    }
+
+    #[test]
+    fn test_no_leading_zero_warning() {
+        let output = Lexer::with_source("0 0.5 10 100.01").lex_all();
+
+        assert!(output.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_on_progress() {
+        let source = "x ".repeat(600);
+        let mut calls = Vec::new();
+
+        let count = Lexer::with_source(&source)
+            .on_progress(|done, total| calls.push((done, total)))
+            .count();
+
+        assert_eq!(1201, count);
+        assert_eq!(vec![(1024, 1200), (1200, 1200)], calls);
+    }
+
+    #[test]
+    fn test_on_progress_small_source() {
+        let mut calls = 0;
+        Lexer::with_source("var x;")
+            .on_progress(|_, _| calls += 1)
+            .for_each(drop);
+
+        assert_eq!(1, calls);
+    }
+
+    #[test]
+    fn test_strict_numbers() {
+        let output = Lexer::with_source("123abc;").strict_numbers().lex_all();
+
+        assert_eq!(
+            Lexeme::Number(NumberLit::new(123., "123abc")),
+            output.tokens[0].lexeme
+        );
+        assert!(output.tokens[0].span.is_n_chars(6));
+        assert_eq!(Lexeme::Semicolon, output.tokens[1].lexeme);
+        assert_eq!(
+            vec![LexError::MalformedNumber {
+                span: output.tokens[0].span
+            }],
+            output.errors
+        );
+    }
+
+    #[test]
+    fn test_strict_numbers_separated() {
+        let output = Lexer::with_source("123 abc 1.5;")
+            .strict_numbers()
+            .lex_all();
+
+        assert!(output.errors.is_empty());
+    }
+
+    #[test]
+    fn test_permissive_numbers() {
+        let output = Lexer::with_source("123abc").lex_all();
+
+        assert_eq!(
+            Lexeme::Number(NumberLit::new(123., "123")),
+            output.tokens[0].lexeme
+        );
+        assert_eq!(
+            Lexeme::Identifier("abc".to_string()),
+            output.tokens[1].lexeme
+        );
+        assert!(output.errors.is_empty());
+    }
+
+    #[test]
+    fn test_eof_span_without_final_newline() {
+        let tkns: Vec<Token> = Lexer::with_source("var x;\nprint x;").collect();
+        let eof = tkns[tkns.len() - 1].span;
+
+        assert!(eof.is_eof());
+        assert_eq!(2, eof.start_line.0);
+        assert_eq!(8, eof.start_col.0);
+        assert_eq!(2, eof.end_line.0);
+
+        let last = Lexer::last_token_span("var x;\nprint x;").unwrap();
+        assert_eq!(Lexeme::Semicolon, tkns[tkns.len() - 2].lexeme);
+        assert_eq!(tkns[tkns.len() - 2].span, last);
+        assert_eq!(eof.start_col.0, last.end_col.0);
+    }
+
+    #[test]
+    fn test_eof_span_with_final_newline() {
+        let tkns: Vec<Token> = Lexer::with_source("var x;\n").collect();
+        let eof = tkns[tkns.len() - 1].span;
+
+        assert!(eof.is_eof());
+        assert_eq!(2, eof.start_line.0);
+        assert_eq!(0, eof.start_col.0);
+
+        let last = Lexer::last_token_span("var x;\n").unwrap();
+        assert!(last.is_newline());
+    }
+
+    #[test]
+    fn test_last_token_span_empty() {
+        assert!(Lexer::last_token_span("").is_none());
+    }
+
+    #[test]
+    fn test_string_delimiters_backtick() {
+        let tkns: Vec<Token> = Lexer::with_source("x = `say \"hi\"`;")
+            .string_delimiters(&[('`', EscapePolicy::Raw)])
+            .collect();
+
+        assert_eq!(Lexeme::String("say \"hi\"".to_string()), tkns[4].lexeme);
+        assert!(tkns[4].span.is_n_chars(10));
+        assert_eq!(Lexeme::Semicolon, tkns[5].lexeme);
+    }
+
+    #[test]
+    fn test_string_delimiters_escapes_per_mode() {
+        let tkns: Vec<Token> = Lexer::with_source(r"`a\`b` 'c\n'")
+            .string_delimiters(&[('`', EscapePolicy::Decoded), ('\'', EscapePolicy::Raw)])
+            .collect();
+
+        assert_eq!(Lexeme::String("a`b".to_string()), tkns[0].lexeme);
+        assert_eq!(Some(EscapePolicy::Decoded), tkns[0].escape_policy());
+        assert_eq!(Lexeme::String(r"c\n".to_string()), tkns[2].lexeme);
+        assert_eq!(Some(EscapePolicy::Raw), tkns[2].escape_policy());
+    }
+
+    #[test]
+    fn test_string_delimiters_unterminated() {
+        let output = Lexer::with_source("x;\n`abc\nd")
+            .string_delimiters(&[('`', EscapePolicy::Raw)])
+            .lex_all();
+
+        let string = &output.tokens[3];
+        assert_eq!(Lexeme::String("abc\nd".to_string()), string.lexeme);
+        assert_eq!(2, string.span.start_line.0);
+        assert_eq!(0, string.span.start_col.0);
+        assert_eq!(3, string.span.end_line.0);
+        assert_eq!(1, string.span.end_col.0);
+        assert_eq!(
+            vec![LexError::UnterminatedString { span: string.span }],
+            output.errors
+        );
+        assert_eq!(Lexeme::Eof, output.tokens[4].lexeme);
+    }
+
+    #[test]
+    fn test_from_bytes_lossy() {
+        let tkns: Vec<Token> = Lexer::from_bytes_lossy(b"var s = \"a\xFFb\xC3\";").collect();
+
+        assert_eq!(
+            Lexeme::String("a\u{fffd}b\u{fffd}".to_string()),
+            tkns[6].lexeme
+        );
+        assert_eq!(8, tkns[6].span.start_col.0);
+        assert_eq!(14, tkns[6].span.end_col.0);
+        assert_eq!(Lexeme::Semicolon, tkns[7].lexeme);
+        assert_eq!(Lexeme::Eof, tkns[8].lexeme);
+
+        let output = Lexer::from_bytes_lossy(b"var x\xFF = 1;").lex_all();
+        let lexemes: Vec<Lexeme> = output.tokens.into_iter().map(|t| t.lexeme).collect();
+        assert_eq!(Lexeme::Identifier("x".to_string()), lexemes[2]);
+        assert_eq!(Lexeme::Whitespace(" ".to_string()), lexemes[3]);
+        assert_eq!(Lexeme::Equal, lexemes[4]);
+        assert_eq!(
+            vec![LexError::UnexpectedChar {
+                c: char::REPLACEMENT_CHARACTER,
+                span: Span::new(Line(1), Column(5))
+            }],
+            output.errors
+        );
+    }
+
+    #[test]
+    fn test_unexpected_chars_are_skipped() {
+        let output = Lexer::with_source("a @$ b").lex_all();
+
+        let kinds = kinds(&output.tokens);
+        assert_eq!(
+            vec![
+                LexemeKind::Identifier,
+                LexemeKind::Whitespace,
+                LexemeKind::Whitespace,
+                LexemeKind::Identifier,
+                LexemeKind::Eof
+            ],
+            kinds
+        );
+        assert_eq!(2, output.errors.len());
+        assert_eq!(
+            LexError::UnexpectedChar {
+                c: '$',
+                span: Span::new(Line(1), Column(3))
+            },
+            output.errors[1]
+        );
+    }
+
+    #[test]
+    fn test_position_encoding_utf16() {
+        let tkns: Vec<Token> = Lexer::with_source("\"\u{1F600}\" x")
+            .position_encoding(PositionEncoding::Utf16)
+            .collect();
+
+        assert!(tkns[0].span.is_n_chars(4));
+        assert_eq!(5, tkns[2].span.start_col.0);
+    }
+
+    #[test]
+    fn test_position_encoding_utf8_bytes() {
+        let tkns: Vec<Token> = Lexer::with_source("\"\u{e9}\u{1F600}\" x")
+            .position_encoding(PositionEncoding::Utf8Bytes)
+            .collect();
+
+        assert!(tkns[0].span.is_n_chars(8));
+        assert_eq!(9, tkns[2].span.start_col.0);
+    }
+
+    #[test]
+    fn test_position_encoding_scalar_values() {
+        let tkns: Vec<Token> = Lexer::with_source("\"\u{1F600}\" x").collect();
+
+        assert!(tkns[0].span.is_n_chars(3));
+        assert_eq!(4, tkns[2].span.start_col.0);
+    }
+
+    #[test]
+    fn test_rewind_one() {
+        let mut lxr = Lexer::with_source("var x;");
+
+        let var = lxr.next().unwrap();
+        assert_eq!(Lexeme::Var, var.lexeme);
+
+        lxr.rewind_one(var);
+        let var = lxr.next().unwrap();
+        assert_eq!(Lexeme::Var, var.lexeme);
+        assert_eq!(0, var.span.start_col.0);
+
+        let ws = lxr.next().unwrap();
+        assert_eq!(Lexeme::Whitespace(" ".to_string()), ws.lexeme);
+    }
+
+    #[test]
+    fn test_rewind_one_twice() {
+        let mut lxr = Lexer::with_source("var x;");
+        let var = lxr.next().unwrap();
+        let ws = lxr.next().unwrap();
+
+        lxr.rewind_one(ws);
+        lxr.rewind_one(var);
+
+        assert_eq!(Lexeme::Var, lxr.next().unwrap().lexeme);
+        assert_eq!(
+            Lexeme::Whitespace(" ".to_string()),
+            lxr.next().unwrap().lexeme
+        );
+        assert_eq!(
+            Lexeme::Identifier("x".to_string()),
+            lxr.next().unwrap().lexeme
+        );
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut lxr = Lexer::with_source("x;");
+
+        assert_eq!(
+            Lexeme::Identifier("x".to_string()),
+            lxr.peek().unwrap().lexeme
+        );
+        let x = lxr.next().unwrap();
+        assert_eq!(Lexeme::Semicolon, lxr.peek().unwrap().lexeme);
+
+        lxr.rewind_one(x);
+        assert_eq!(
+            Lexeme::Identifier("x".to_string()),
+            lxr.peek().unwrap().lexeme
+        );
+        assert_eq!(3, lxr.count());
+    }
+
+    #[test]
+    fn test_from_char_iter() {
+        let source = "var x";
+        let expected: Vec<Token> = Lexer::with_source(source).collect();
+        let tkns: Vec<Token> = Lexer::from_char_iter(source.chars()).collect();
+
+        assert_eq!(expected.len(), tkns.len());
+        for (e, t) in expected.iter().zip(tkns.iter()) {
+            assert_eq!(e.lexeme, t.lexeme);
+            assert_eq!(e.span, t.span);
+        }
+    }
+
+    #[test]
+    fn test_from_char_iter_generated() {
+        let chars = "print 1;".chars().map(|c| c.to_ascii_lowercase());
+        let tkns: Vec<Token> = Lexer::from_char_iter(chars).collect();
+
+        assert_eq!(Lexeme::Print, tkns[0].lexeme);
+        assert_eq!(Lexeme::Number(NumberLit::new(1., "1")), tkns[2].lexeme);
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let source = "var x = \"h\u{e9}llo\";".as_bytes();
+        let tkns: Vec<Token> = Lexer::from_bytes(source).unwrap().collect();
+
+        assert_eq!(Lexeme::String("h\u{e9}llo".to_string()), tkns[6].lexeme);
+        assert_eq!(8, tkns[6].span.start_col.0);
+        assert_eq!(15, tkns[6].span.end_col.0);
+        assert_eq!(Lexeme::Semicolon, tkns[7].lexeme);
+    }
+
+    #[test]
+    fn test_from_bytes_invalid_utf8() {
+        let source = b"var x\xff";
+        assert!(Lexer::from_bytes(source).is_err());
+    }
+
     #[test]
     fn test_intoiter() {
         let source = "var x = \"test\"";
@@ -3,13 +3,61 @@
 #![deny(missing_docs)]
 #![deny(unreachable_code)]
 
+mod analysis;
+#[cfg(feature = "arena")]
+mod arena;
 mod chars;
+mod classifier;
+mod concat;
+mod diagnostic;
+mod encoding;
+mod error;
+mod escape;
+mod highlight;
+mod interner;
+mod kind;
 mod lexeme;
 mod lexer;
+mod literal;
+mod options;
+mod overflow;
+mod owned;
+#[cfg(feature = "parser_helpers")]
+mod parser_helpers;
+mod source;
+mod source_map;
 mod span;
+mod stats;
+#[cfg(feature = "async")]
+mod stream;
+mod test_util;
 mod token;
+mod visitor;
 
+pub use crate::analysis::*;
+#[cfg(feature = "arena")]
+pub use crate::arena::*;
+pub use crate::classifier::*;
+pub use crate::concat::*;
+pub use crate::diagnostic::*;
+pub use crate::encoding::*;
+pub use crate::error::*;
+pub use crate::escape::*;
+pub use crate::highlight::*;
+pub use crate::interner::*;
+pub use crate::kind::*;
 pub use crate::lexeme::*;
 pub use crate::lexer::*;
+pub use crate::literal::*;
+pub use crate::overflow::*;
+pub use crate::owned::*;
+#[cfg(feature = "parser_helpers")]
+pub use crate::parser_helpers::*;
+pub use crate::source_map::*;
 pub use crate::span::*;
+pub use crate::stats::*;
+#[cfg(feature = "async")]
+pub use crate::stream::*;
+pub use crate::test_util::*;
 pub use crate::token::*;
+pub use crate::visitor::*;
@@ -0,0 +1,43 @@
+use crate::{Lexeme, Lexer, Span};
+
+/// The value of a string or number literal.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LiteralValue {
+    /// A string literal
+    Str(String),
+    /// A number literal
+    Num(f64),
+}
+
+impl Lexer {
+    /// Returns an iterator over the values of the string and number
+    /// literals of the source string, with their positions.
+    pub fn literals(source: &str) -> impl Iterator<Item = (LiteralValue, Span)> + '_ {
+        Lexer::with_source(source).filter_map(|tkn| match tkn.lexeme {
+            Lexeme::String(s) => Some((LiteralValue::Str(s), tkn.span)),
+            Lexeme::Number(n) => Some((LiteralValue::Num(n.value), tkn.span)),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literals() {
+        let literals: Vec<(LiteralValue, Span)> = Lexer::literals("var x = \"hi\" + 3;").collect();
+
+        assert_eq!(2, literals.len());
+        assert_eq!(LiteralValue::Str("hi".to_string()), literals[0].0);
+        assert_eq!(8, literals[0].1.start_col.0);
+        assert_eq!(LiteralValue::Num(3.), literals[1].0);
+        assert_eq!(15, literals[1].1.start_col.0);
+    }
+
+    #[test]
+    fn test_literals_none() {
+        assert_eq!(0, Lexer::literals("print x;").count());
+    }
+}
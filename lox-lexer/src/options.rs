@@ -0,0 +1,31 @@
+use crate::{EscapePolicy, LexemeKind, OverflowMode, PositionEncoding};
+
+/// The options which drive the behaviour of the lexer.
+#[derive(Clone, Default)]
+pub(crate) struct Options {
+    pub(crate) emit_bof: bool, // emit a 'bof' token before any other token
+    pub(crate) merge_whitespace_and_newlines: bool, // emit one 'whitespace' token for whitespaces and new lines
+    pub(crate) strict_numbers: bool, // report a number immediately followed by an identifier
+    pub(crate) digit_separators: bool, // read the underscores between digits as separators
+    pub(crate) decimal_comma: bool, // read a "," flanked by digits, outside of parentheses, as a decimal separator
+    pub(crate) lint_mixed_indentation: bool, // report leading whitespaces which mix tabs and spaces
+    pub(crate) max_line_len: Option<usize>, // record the lines longer than this length
+    pub(crate) lint_line_len: Option<usize>, // report the lines longer than this length
+    pub(crate) regex_literals: bool, // read a "/" in expression position as the start of a regex
+    pub(crate) exponents: bool,     // read the exponent of a number, e.g. "1e3"
+    pub(crate) ranges: bool,        // read ".." as a 'dot-dot' token
+    pub(crate) eof_on_last_line: bool, // emit the 'eof' token at the end of the last line
+    pub(crate) crlf_newlines: bool, // read a "\r\n" as one 'new line' token
+    pub(crate) heredocs: bool,      // read a "<<TAG" at the end of a line as a heredoc
+    pub(crate) ellipsis: bool,      // read "..." as an 'ellipsis' token
+    pub(crate) recover_to_statement: bool, // skip to the next statement boundary after an error
+    pub(crate) source_id: u32,      // the id of the source, carried by the tokens
+    pub(crate) integer_overflow: Option<OverflowMode>, // check the integer literals which do not fit in an i64
+    pub(crate) case_insensitive_keywords: bool, // match the keywords regardless of their case
+    pub(crate) soft_keywords: Option<Vec<String>>, // the keywords which are read as identifiers
+    pub(crate) asi_after: Option<Vec<LexemeKind>>, // tag the new lines after these kinds as permitting ASI
+    pub(crate) string_delimiters: Option<Vec<(char, EscapePolicy)>>, // the string delimiters, instead of the double quote
+    pub(crate) escape_policy: EscapePolicy, // keep the escapes of the strings raw or decode them
+    pub(crate) position_encoding: PositionEncoding, // how the columns are counted
+    pub(crate) buffered_payloads: bool, // keep the text payloads in the context buffer, not in the tokens
+}
@@ -0,0 +1,9 @@
+/// How the lexer handles an integer literal which does not fit in an `i64`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OverflowMode {
+    /// The literal is a float, which may lose precision, with a warning
+    #[default]
+    ToFloat,
+    /// The literal is reported as an error
+    Error,
+}
@@ -0,0 +1,131 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::lexer::Context;
+use crate::{Lexer, LexerIter, Token};
+
+/// A lexer which owns its source string, so it has no lifetime and
+/// can be moved around, e.g. into another thread, before it is iterated.
+#[derive(Clone, Debug)]
+pub struct OwnedLexer {
+    source: String, // the source string
+}
+
+impl OwnedLexer {
+    /// Returns the source string of the lexer.
+    #[inline]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+/// Iterates over the characters of an owned string.
+struct OwnedChars {
+    source: String, // the source string
+    offset: usize,  // the byte offset of the next character
+}
+
+impl Iterator for OwnedChars {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = self.source[self.offset..].chars().next()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+}
+
+impl IntoIterator for OwnedLexer {
+    type Item = Token;
+    type IntoIter = LexerIter<'static>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let bytes_total = self.source.len();
+        let chars = OwnedChars {
+            source: self.source,
+            offset: 0,
+        };
+
+        let mut ctx = Context::from_chars(chars);
+        ctx.bytes_total = bytes_total;
+        LexerIter::new(ctx)
+    }
+}
+
+impl Lexer {
+    /// Returns a lexer which owns the source string and yields
+    /// the owned tokens of the source when it is iterated.
+    #[inline]
+    pub fn into_iter_owned(source: String) -> OwnedLexer {
+        OwnedLexer { source }
+    }
+
+    /// Reads a file and returns a lexer which owns its content. It fails
+    /// when the file cannot be read or when its content is not valid UTF-8.
+    pub fn try_from_path<P: AsRef<Path>>(path: P) -> io::Result<OwnedLexer> {
+        let source = fs::read_to_string(path)?;
+        Ok(Lexer::into_iter_owned(source))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::thread;
+
+    #[test]
+    fn test_into_iter_owned() {
+        let source = "var a = \"h\u{e9}\";".to_string();
+        let expected: Vec<Token> = Lexer::with_source(&source).collect();
+
+        let lxr = Lexer::into_iter_owned(source);
+        let tkns: Vec<Token> = lxr.into_iter().collect();
+
+        assert_eq!(expected, tkns);
+    }
+
+    #[test]
+    fn test_into_iter_owned_in_thread() {
+        let lxr = Lexer::into_iter_owned("print 1 + 2;".to_string());
+
+        let handle = thread::spawn(move || {
+            let mut tkns = Vec::new();
+            for tkn in lxr {
+                tkns.push(tkn);
+            }
+            tkns
+        });
+
+        let tkns = handle.join().unwrap();
+        assert_eq!(Lexer::with_source("print 1 + 2;").collect::<Vec<_>>(), tkns);
+    }
+
+    #[test]
+    fn test_try_from_path() {
+        let path = env::temp_dir().join("lox_lexer_try_from_path.lox");
+        fs::write(&path, "var a = 1;").unwrap();
+
+        let lxr = Lexer::try_from_path(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!("var a = 1;", lxr.source());
+        assert_eq!(9, lxr.into_iter().count());
+    }
+
+    #[test]
+    fn test_try_from_path_errors() {
+        let path = env::temp_dir().join("lox_lexer_try_from_path_missing.lox");
+        let err = Lexer::try_from_path(&path).unwrap_err();
+        assert_eq!(io::ErrorKind::NotFound, err.kind());
+
+        let path = env::temp_dir().join("lox_lexer_try_from_path_invalid.lox");
+        fs::write(&path, b"var \xff;").unwrap();
+
+        let err = Lexer::try_from_path(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+    }
+}
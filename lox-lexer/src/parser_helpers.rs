@@ -0,0 +1,216 @@
+use crate::{Lexeme, LexemeKind, LexerIter, Token};
+
+/// Extends the lexemes with the expectations of the Lox grammar, so the
+/// parsers do not need to redefine them.
+pub trait LexemeExt {
+    /// Returns true if the lexeme can start an expression: a literal,
+    /// an identifier, `this`, `super`, a grouping or an unary operator.
+    fn starts_expression(&self) -> bool;
+
+    /// Returns true if the lexeme can start a declaration or a statement,
+    /// including an expression statement.
+    fn starts_statement(&self) -> bool;
+
+    /// Returns true if the lexeme can start an assignment target, which
+    /// is a variable or a property access (`a = ..`, `this.a = ..`).
+    fn is_assignable_target_start(&self) -> bool;
+
+    /// Returns true for the binary operators, including the logical
+    /// `and` and `or`, but not the assignment.
+    fn is_binary_op(&self) -> bool;
+
+    /// Returns true for the unary operators.
+    fn is_unary_op(&self) -> bool;
+
+    /// Returns true for the assignment operators. Lox has only the
+    /// plain `=`, there is no compound assignment such as `+=`.
+    fn is_assignment_op(&self) -> bool;
+
+    /// Returns true for the comparison operators, including
+    /// the equality `==` and `!=`.
+    fn is_comparison_op(&self) -> bool;
+}
+
+impl LexemeExt for Lexeme {
+    fn starts_expression(&self) -> bool {
+        use Lexeme::*;
+
+        matches!(
+            self,
+            Number(_)
+                | String(_)
+                | Regex(_)
+                | Heredoc { .. }
+                | Identifier(_)
+                | Symbol(_)
+                | True
+                | False
+                | Nil
+                | This
+                | Super
+                | LeftParen
+        ) || self.is_unary_op()
+    }
+
+    fn starts_statement(&self) -> bool {
+        use Lexeme::*;
+
+        matches!(
+            self,
+            Class | Fun | Var | For | If | Print | Return | While | LeftBrace
+        ) || self.starts_expression()
+    }
+
+    fn is_assignable_target_start(&self) -> bool {
+        matches!(
+            self,
+            Lexeme::Identifier(_) | Lexeme::Symbol(_) | Lexeme::This | Lexeme::Super
+        )
+    }
+
+    fn is_binary_op(&self) -> bool {
+        use Lexeme::*;
+
+        matches!(
+            self,
+            Minus
+                | Plus
+                | Slash
+                | Star
+                | BangEqual
+                | EqualEqual
+                | Greater
+                | GreaterEqual
+                | Less
+                | LessEqual
+                | And
+                | Or
+        )
+    }
+
+    fn is_unary_op(&self) -> bool {
+        matches!(self, Lexeme::Bang | Lexeme::Minus)
+    }
+
+    fn is_assignment_op(&self) -> bool {
+        matches!(self, Lexeme::Equal)
+    }
+
+    fn is_comparison_op(&self) -> bool {
+        use Lexeme::*;
+
+        matches!(
+            self,
+            BangEqual | EqualEqual | Greater | GreaterEqual | Less | LessEqual
+        )
+    }
+}
+
+impl<'a> LexerIter<'a> {
+    /// Pulls the next token and returns it if it has the expected kind,
+    /// otherwise returns the actual token as an error. Once the tokens are
+    /// exhausted, the actual token is an 'eof' token at the end of the source.
+    pub fn expect(&mut self, kind: LexemeKind) -> Result<Token, Token> {
+        let tkn = self.next().unwrap_or_else(|| Token::new_eof(self.ctx.span));
+
+        if tkn.lexeme.kind() == kind {
+            Ok(tkn)
+        } else {
+            Err(tkn)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Lexer, NumberLit};
+
+    #[test]
+    fn test_starts_expression() {
+        assert!(Lexeme::Number(NumberLit::from_value(1.)).starts_expression());
+        assert!(Lexeme::Identifier("x".to_string()).starts_expression());
+        assert!(Lexeme::LeftParen.starts_expression());
+        assert!(Lexeme::Bang.starts_expression());
+        assert!(!Lexeme::Semicolon.starts_expression());
+        assert!(!Lexeme::Var.starts_expression());
+        assert!(!Lexeme::Plus.starts_expression());
+    }
+
+    #[test]
+    fn test_starts_statement() {
+        assert!(Lexeme::Var.starts_statement());
+        assert!(Lexeme::LeftBrace.starts_statement());
+        assert!(Lexeme::String("s".to_string()).starts_statement());
+        assert!(!Lexeme::Semicolon.starts_statement());
+        assert!(!Lexeme::Else.starts_statement());
+        assert!(!Lexeme::RightBrace.starts_statement());
+    }
+
+    #[test]
+    fn test_is_assignable_target_start() {
+        assert!(Lexeme::Identifier("x".to_string()).is_assignable_target_start());
+        assert!(Lexeme::This.is_assignable_target_start());
+        assert!(!Lexeme::Number(NumberLit::from_value(1.)).is_assignable_target_start());
+    }
+
+    #[test]
+    fn test_expect() {
+        let mut lxr = Lexer::with_source("print(x);");
+
+        assert_eq!(Lexeme::Print, lxr.expect(LexemeKind::Print).unwrap().lexeme);
+        assert!(lxr.expect(LexemeKind::LeftParen).is_ok());
+        assert_eq!(
+            Lexeme::Identifier("x".to_string()),
+            lxr.expect(LexemeKind::Identifier).unwrap().lexeme
+        );
+
+        let actual = lxr.expect(LexemeKind::Semicolon).unwrap_err();
+        assert_eq!(Lexeme::RightParen, actual.lexeme);
+        assert_eq!(7, actual.span.start_col.0);
+
+        assert!(lxr.expect(LexemeKind::Semicolon).is_ok());
+        assert!(lxr.expect(LexemeKind::Eof).is_ok());
+    }
+
+    #[test]
+    fn test_expect_exhausted() {
+        let mut lxr = Lexer::with_source("x");
+        lxr.by_ref().for_each(drop);
+
+        let actual = lxr.expect(LexemeKind::Identifier).unwrap_err();
+        assert_eq!(Lexeme::Eof, actual.lexeme);
+        assert_eq!(1, actual.span.start_col.0);
+    }
+
+    #[test]
+    fn test_operators() {
+        assert!(Lexeme::Plus.is_binary_op());
+        assert!(Lexeme::Minus.is_binary_op());
+        assert!(Lexeme::Or.is_binary_op());
+        assert!(!Lexeme::Equal.is_binary_op());
+        assert!(!Lexeme::Bang.is_binary_op());
+
+        assert!(Lexeme::Bang.is_unary_op());
+        assert!(Lexeme::Minus.is_unary_op());
+        assert!(!Lexeme::Plus.is_unary_op());
+    }
+
+    #[test]
+    fn test_assignment_and_comparison_ops() {
+        assert!(Lexeme::Equal.is_assignment_op());
+        assert!(!Lexeme::Equal.is_comparison_op());
+
+        assert!(Lexeme::EqualEqual.is_comparison_op());
+        assert!(!Lexeme::EqualEqual.is_assignment_op());
+
+        assert!(Lexeme::Less.is_comparison_op());
+        assert!(!Lexeme::Less.is_assignment_op());
+
+        // There is no compound assignment, "+=" is a 'plus' and an 'equal'.
+        let tkns: Vec<Token> = Lexer::with_source("+=").collect();
+        assert!(!tkns[0].lexeme.is_assignment_op());
+        assert!(!tkns[0].lexeme.is_comparison_op());
+        assert!(tkns[1].lexeme.is_assignment_op());
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+
+/// The source of characters for the lexer. It wraps any iterator of
+/// characters and allows looking ahead at the next characters.
+pub(crate) struct Source<'a> {
+    chars: Chars<'a>,          // the characters not read yet
+    lookahead: VecDeque<char>, // the characters peeked but not consumed
+}
+
+/// The characters not read yet. The characters of a string, the common
+/// case, are read without the dynamic dispatch of any other iterator.
+enum Chars<'a> {
+    Str(std::str::Chars<'a>),
+    Iter(Box<dyn Iterator<Item = char> + 'a>),
+}
+
+impl<'a> Chars<'a> {
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        match self {
+            Chars::Str(chars) => chars.next(),
+            Chars::Iter(chars) => chars.next(),
+        }
+    }
+}
+
+impl<'a> Source<'a> {
+    /// Creates a new source from an iterator of characters.
+    pub(crate) fn new(chars: impl Iterator<Item = char> + 'a) -> Self {
+        Self {
+            chars: Chars::Iter(Box::new(chars)),
+            lookahead: VecDeque::new(),
+        }
+    }
+
+    /// Creates a new source from the characters of a string.
+    pub(crate) fn from_str(text: &'a str) -> Self {
+        Self {
+            chars: Chars::Str(text.chars()),
+            lookahead: VecDeque::new(),
+        }
+    }
+
+    /// Returns the n-th next character, without consuming it.
+    pub(crate) fn peek_nth(&mut self, n: usize) -> Option<char> {
+        while self.lookahead.len() <= n {
+            let c = self.chars.next()?;
+            self.lookahead.push_back(c);
+        }
+
+        self.lookahead.get(n).copied()
+    }
+
+    /// Returns the next character, without consuming it.
+    #[inline]
+    pub(crate) fn peek(&mut self) -> Option<char> {
+        self.peek_nth(0)
+    }
+}
+
+impl<'a> Iterator for Source<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lookahead.pop_front().or_else(|| self.chars.next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peek_and_next() {
+        check_peek_and_next(&mut Source::new("abc".chars()));
+        check_peek_and_next(&mut Source::from_str("abc"));
+    }
+
+    fn check_peek_and_next(source: &mut Source) {
+        assert_eq!(Some('a'), source.peek());
+        assert_eq!(Some('c'), source.peek_nth(2));
+        assert_eq!(None, source.peek_nth(3));
+        assert_eq!(Some('a'), source.next());
+        assert_eq!(Some('b'), source.peek());
+        assert_eq!(Some('b'), source.next());
+        assert_eq!(Some('c'), source.next());
+        assert_eq!(None, source.peek());
+        assert_eq!(None, source.next());
+    }
+}
@@ -0,0 +1,220 @@
+use std::ops::Range;
+
+use crate::chars::*;
+use crate::{Lexer, Span};
+
+/// Maps the lines and columns used by the spans to byte offsets
+/// in the source, and back. The lines start at 1 and the columns
+/// are counted in characters from 0, the same as in a [crate::Span].
+pub struct SourceMap<'a> {
+    source: &'a str,         // the source string
+    line_starts: Vec<usize>, // the byte offset where each line starts
+}
+
+impl<'a> SourceMap<'a> {
+    /// Creates the source map of a source string.
+    pub fn new(source: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            source
+                .char_indices()
+                .filter(|(_, c)| *c == CHAR_NEWLINE)
+                .map(|(i, _)| i + 1),
+        );
+
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    /// Returns the number of lines in the source.
+    #[inline]
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the text of a line, without the new line.
+    fn line_text(&self, line: usize) -> Option<&'a str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |next| next - 1);
+
+        Some(&self.source[start..end])
+    }
+
+    /// Returns the length of a line in characters, without the new line,
+    /// or `None` for an unknown line.
+    pub fn line_len(&self, line: usize) -> Option<usize> {
+        self.line_text(line).map(|text| text.chars().count())
+    }
+
+    /// Returns the byte offset of a line and column, or `None` if the
+    /// position is outside of the source. The column can be the one right
+    /// after the last character of the line.
+    pub fn line_col_to_byte(&self, line: usize, col: usize) -> Option<usize> {
+        let text = self.line_text(line)?;
+        let start = self.line_starts[line - 1];
+
+        text.char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(text.len()))
+            .nth(col)
+            .map(|i| start + i)
+    }
+
+    /// Returns the byte range of a span, or `None` if the span is
+    /// outside of the source, e.g. to slice the text of a token.
+    pub fn byte_range(&self, span: Span) -> Option<Range<usize>> {
+        let start = self.line_col_to_byte(span.start_line.0, span.start_col.0)?;
+        let end = self.line_col_to_byte(span.end_line.0, span.end_col.0)?;
+
+        Some(start..end)
+    }
+
+    /// Returns the column of a position in UTF-16 code units, as used by the
+    /// LSP, from the byte offset of the position in its line. The offset is
+    /// clamped to the end of the line, and it is `None` for an unknown line.
+    pub fn byte_to_utf16_col(&self, line: usize, byte: usize) -> Option<usize> {
+        let text = self.line_text(line)?;
+
+        let col = text
+            .char_indices()
+            .take_while(|(i, _)| *i < byte)
+            .map(|(_, c)| c.len_utf16())
+            .sum();
+
+        Some(col)
+    }
+
+    /// Returns the line and column of a byte offset. An offset past
+    /// the end of the source is clamped to the end of the source.
+    pub fn byte_to_line_col(&self, byte: usize) -> (usize, usize) {
+        let byte = byte.min(self.source.len());
+        let line = match self.line_starts.binary_search(&byte) {
+            Ok(index) => index + 1,
+            Err(index) => index,
+        };
+
+        let start = self.line_starts[line - 1];
+        let col = self.source.as_bytes()[start..byte]
+            .iter()
+            .filter(|b| (**b as i8) >= -0x40) // skip the utf-8 continuation bytes
+            .count();
+
+        (line, col)
+    }
+}
+
+impl Lexer {
+    /// Returns the source map of a source string.
+    #[inline]
+    pub fn source_map(source: &str) -> SourceMap<'_> {
+        SourceMap::new(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "var a;\n\"h\u{e9}\" + b;\n\nx";
+
+    #[test]
+    fn test_line_col_to_byte() {
+        let map = Lexer::source_map(SOURCE);
+
+        assert_eq!(4, map.line_count());
+        assert_eq!(Some(0), map.line_col_to_byte(1, 0));
+        assert_eq!(Some(4), map.line_col_to_byte(1, 4));
+        assert_eq!(Some(6), map.line_col_to_byte(1, 6));
+        assert_eq!(Some(7), map.line_col_to_byte(2, 0));
+        assert_eq!(Some(11), map.line_col_to_byte(2, 3));
+        assert_eq!(Some(13), map.line_col_to_byte(2, 5));
+        assert_eq!(Some(18), map.line_col_to_byte(3, 0));
+        assert_eq!(Some(19), map.line_col_to_byte(4, 0));
+    }
+
+    #[test]
+    fn test_line_col_to_byte_outside() {
+        let map = Lexer::source_map(SOURCE);
+
+        assert_eq!(None, map.line_col_to_byte(0, 0));
+        assert_eq!(None, map.line_col_to_byte(1, 7));
+        assert_eq!(None, map.line_col_to_byte(3, 1));
+        assert_eq!(None, map.line_col_to_byte(5, 0));
+    }
+
+    #[test]
+    fn test_line_len() {
+        let map = Lexer::source_map(SOURCE);
+
+        assert_eq!(Some(6), map.line_len(1));
+        assert_eq!(Some(9), map.line_len(2));
+        assert_eq!(Some(0), map.line_len(3));
+        assert_eq!(Some(1), map.line_len(4));
+        assert_eq!(None, map.line_len(0));
+        assert_eq!(None, map.line_len(5));
+    }
+
+    #[test]
+    fn test_byte_to_line_col() {
+        let map = Lexer::source_map(SOURCE);
+
+        assert_eq!((1, 0), map.byte_to_line_col(0));
+        assert_eq!((1, 4), map.byte_to_line_col(4));
+        assert_eq!((1, 6), map.byte_to_line_col(6));
+        assert_eq!((2, 0), map.byte_to_line_col(7));
+        assert_eq!((2, 3), map.byte_to_line_col(11));
+        assert_eq!((2, 5), map.byte_to_line_col(13));
+        assert_eq!((3, 0), map.byte_to_line_col(18));
+        assert_eq!((4, 1), map.byte_to_line_col(20));
+        assert_eq!((4, 1), map.byte_to_line_col(100));
+    }
+
+    #[test]
+    fn test_byte_range() {
+        let map = Lexer::source_map(SOURCE);
+        let tokens: Vec<_> = Lexer::with_source(SOURCE).collect();
+
+        let text: Vec<&str> = tokens
+            .iter()
+            .map(|tkn| &SOURCE[map.byte_range(tkn.span).unwrap()])
+            .collect();
+        assert_eq!("var", text[0]);
+        assert_eq!("\n", text[4]);
+        assert_eq!("\"h\u{e9}\"", text[5]);
+        assert_eq!("", text[text.len() - 1]);
+        assert_eq!(SOURCE, text.concat());
+    }
+
+    #[test]
+    fn test_byte_to_utf16_col() {
+        let source = "x\n\"\u{1d11e}\" + \u{e9};";
+        let map = Lexer::source_map(source);
+
+        assert_eq!(Some(0), map.byte_to_utf16_col(1, 0));
+        assert_eq!(Some(1), map.byte_to_utf16_col(2, 1));
+        assert_eq!(Some(3), map.byte_to_utf16_col(2, 5));
+        assert_eq!(Some(8), map.byte_to_utf16_col(2, 11));
+        assert_eq!(Some(9), map.byte_to_utf16_col(2, 100));
+        assert_eq!(None, map.byte_to_utf16_col(3, 0));
+
+        // The char column of the "+" is 4, but it is 5 in UTF-16 code units.
+        let (line, col) = map.byte_to_line_col(source.find('+').unwrap());
+        assert_eq!((2, 4), (line, col));
+        assert_eq!(Some(5), map.byte_to_utf16_col(2, 7));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let map = Lexer::source_map(SOURCE);
+
+        for (byte, _) in SOURCE.char_indices() {
+            let (line, col) = map.byte_to_line_col(byte);
+            assert_eq!(Some(byte), map.line_col_to_byte(line, col));
+        }
+    }
+}
@@ -1,22 +1,32 @@
+use std::convert::TryFrom;
 use std::fmt::Debug;
 
-/// The line in the source stream
-#[derive(Clone, Copy, PartialEq)]
+use crate::SourceMap;
+
+/// The line in the source stream, starting at 1.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Line(pub usize);
 
-/// The column in the source stream
-#[derive(Clone, Copy, PartialEq)]
+/// The column in the source stream, starting at 0: the first character
+/// of a line occupies the columns 0..1. The columns are 0-based everywhere,
+/// in the spans, in the messages of the errors and in the [crate::SourceMap],
+/// so a tool which shows 1-based columns adds 1 when it displays them.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Column(pub usize);
 
-/// The position in the stream
-#[derive(Clone, Copy, PartialEq)]
+/// The position in the stream. The spans are ordered by their start
+/// and then by their end. The start is inclusive and the end exclusive.
+/// See [Line] and [Column] for their origins.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Span {
-    start_line: Line,
+    pub(crate) start_line: Line,
     pub(crate) start_col: Column,
-    end_line: Line,
+    pub(crate) end_line: Line,
     pub(crate) end_col: Column,
 }
 
+/// The empty span at the start of the source, line 1 and column 0,
+/// where the first token starts.
 impl Default for Span {
     fn default() -> Self {
         Self {
@@ -35,7 +45,7 @@ impl Span {
             start_line: l,
             start_col: c,
             end_line: l,
-            end_col: Column(c.0 + 1),
+            end_col: Column(c.0.saturating_add(1)),
         }
     }
 
@@ -80,10 +90,25 @@ impl Span {
         self.end_col.0 == self.start_col.0
     }
 
-    /// Increments the coumn of a span
+    /// Returns true if the position is inside the span. The start of the
+    /// span is inclusive and its end is exclusive, so an empty span does
+    /// not contain any position.
+    pub fn contains(&self, line: usize, col: usize) -> bool {
+        let pos = (line, col);
+        (self.start_line.0, self.start_col.0) <= pos && pos < (self.end_line.0, self.end_col.0)
+    }
+
+    /// Returns the `(line, col)` where a single caret points at the span,
+    /// which is its start, so all the tools put the `^` at the same place.
+    #[inline]
+    pub fn caret_position(&self) -> (usize, usize) {
+        (self.start_line.0, self.start_col.0)
+    }
+
+    /// Increments the coumn of a span, saturating at `usize::MAX`.
     #[inline]
     pub fn incr_col_n(&mut self, n: usize) {
-        self.end_col = Column(self.end_col.0 + n);
+        self.end_col = Column(self.end_col.0.saturating_add(n));
     }
 
     /// Increments the column of a span
@@ -92,14 +117,132 @@ impl Span {
         self.incr_col_n(1)
     }
 
-    /// Increment the line of a span
+    /// Increment the line of a span, saturating at `usize::MAX`.
     pub fn incr_line(&mut self) {
-        self.end_line = Line(self.end_line.0 + 1);
+        self.end_line = Line(self.end_line.0.saturating_add(1));
         self.end_col = Column(0);
     }
 
+    /// Returns the span extended by a number of characters at its end, on the same line.
+    pub fn grow(&self, chars: usize) -> Span {
+        let mut s = *self;
+        s.incr_col_n(chars);
+        s
+    }
+
+    /// Returns the span shrunk by a number of characters at its end, on the same line.
+    /// The end is clamped at the start of the span, or at the start of the
+    /// line for a multi-line span.
+    pub fn narrow(&self, chars: usize) -> Span {
+        let min = if self.is_one_line() {
+            self.start_col.0
+        } else {
+            0
+        };
+
+        let mut s = *self;
+        s.end_col = Column(self.end_col.0.saturating_sub(chars).max(min));
+        s
+    }
+
+    /// Returns the span relative to the start of an origin span, e.g. to
+    /// convert the span of a token lexed with [crate::Lexer::with_source_range]
+    /// back to the coordinates of the snippet. The lines of the snippet start
+    /// at 1 and only the columns on its first line are moved. A position
+    /// before the origin is clamped to the start of the snippet.
+    pub fn relative_to(&self, origin: Span) -> Span {
+        let relative = |line: Line, col: Column| {
+            if line <= origin.start_line {
+                let col = if line == origin.start_line {
+                    col.0.saturating_sub(origin.start_col.0)
+                } else {
+                    0
+                };
+                (Line(1), Column(col))
+            } else {
+                (Line(line.0 - origin.start_line.0 + 1), col)
+            }
+        };
+
+        let (start_line, start_col) = relative(self.start_line, self.start_col);
+        let (end_line, end_col) = relative(self.end_line, self.end_col);
+        Span {
+            start_line,
+            start_col,
+            end_line,
+            end_col,
+        }
+    }
+
+    /// Returns the span moved to start at the start of an origin span, the
+    /// inverse of [Span::relative_to], e.g. to position the span of a token
+    /// of a snippet in the larger source.
+    pub fn shift(&self, origin: Span) -> Span {
+        let absolute = |line: Line, col: Column| {
+            if line.0 <= 1 {
+                (
+                    origin.start_line,
+                    Column(origin.start_col.0.saturating_add(col.0)),
+                )
+            } else {
+                (Line(origin.start_line.0.saturating_add(line.0 - 1)), col)
+            }
+        };
+
+        let (start_line, start_col) = absolute(self.start_line, self.start_col);
+        let (end_line, end_col) = absolute(self.end_line, self.end_col);
+        Span {
+            start_line,
+            start_col,
+            end_line,
+            end_col,
+        }
+    }
+
+    /// Splits the span into one single-line span for each line it covers,
+    /// e.g. to highlight a multi-line string line by line. The first span goes
+    /// from the start of the span to the end of its line, the interior ones
+    /// cover their whole lines and the last one ends at the end of the span.
+    /// The lengths of the lines, without the new lines, are read from the
+    /// source map of the source; an unknown line is taken as empty.
+    pub fn per_line_spans(&self, map: &SourceMap<'_>) -> Vec<Span> {
+        if self.is_one_line() {
+            return vec![*self];
+        }
+
+        (self.start_line.0..=self.end_line.0)
+            .map(|line| {
+                let start_col = if line == self.start_line.0 {
+                    self.start_col
+                } else {
+                    Column(0)
+                };
+                let end_col = if line == self.end_line.0 {
+                    self.end_col
+                } else {
+                    Column(map.line_len(line).unwrap_or(0).max(start_col.0))
+                };
+
+                Span {
+                    start_line: Line(line),
+                    start_col,
+                    end_line: Line(line),
+                    end_col,
+                }
+            })
+            .collect()
+    }
+
     /// Completes a span and starts a new one.
+    ///
+    /// The end of a span must never be before its start; any API which
+    /// moves a position backwards has to keep this invariant, otherwise
+    /// the completed span and the next one are corrupted.
     pub fn complete(&mut self) -> Self {
+        debug_assert!(
+            (self.end_line, self.end_col) >= (self.start_line, self.start_col),
+            "the span ends before it starts"
+        );
         let s = *self;
 
         self.start_line = self.end_line;
@@ -111,6 +254,36 @@ impl Span {
     }
 }
 
+/// Converts a position to u32, saturating at u32::MAX.
+#[inline]
+fn saturate(n: usize) -> u32 {
+    u32::try_from(n).unwrap_or(u32::MAX)
+}
+
+impl Span {
+    /// Packs the span into a flat array, e.g. for passing it over FFI.
+    /// The layout is `[start_line, start_col, end_line, end_col]`.
+    /// Any position which exceeds `u32::MAX` is saturated to `u32::MAX`.
+    pub fn pack(&self) -> [u32; 4] {
+        [
+            saturate(self.start_line.0),
+            saturate(self.start_col.0),
+            saturate(self.end_line.0),
+            saturate(self.end_col.0),
+        ]
+    }
+
+    /// Unpacks a span from a flat array created by [Span::pack].
+    pub fn unpack(packed: [u32; 4]) -> Self {
+        Span {
+            start_line: Line(packed[0] as usize),
+            start_col: Column(packed[1] as usize),
+            end_line: Line(packed[2] as usize),
+            end_col: Column(packed[3] as usize),
+        }
+    }
+}
+
 impl Debug for Span {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.is_one_line() {
@@ -132,6 +305,7 @@ impl Debug for Span {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Lexer;
 
     #[test]
     fn test_new() {
@@ -190,6 +364,186 @@ mod tests {
         assert_eq!(0, s.end_col.0);
     }
 
+    #[test]
+    fn test_relative_to() {
+        let origin = Span::new(Line(3), Column(10));
+
+        let s = Span::new(Line(3), Column(12)).grow(2);
+        assert_eq!(Span::new(Line(1), Column(2)).grow(2), s.relative_to(origin));
+
+        let mut s = Span::new(Line(3), Column(14));
+        s.incr_line();
+        s.incr_col_n(4);
+        let mut expected = Span::new(Line(1), Column(4));
+        expected.incr_line();
+        expected.incr_col_n(4);
+        assert_eq!(expected, s.relative_to(origin));
+
+        let s = Span::new(Line(5), Column(1));
+        assert_eq!(Span::new(Line(3), Column(1)), s.relative_to(origin));
+    }
+
+    #[test]
+    fn test_relative_to_shift_round_trip() {
+        let source = "print 1;\nvar s = \"a\nb\"; x";
+        let start = source.find("\"a").unwrap();
+        let origin = Span::new(Line(2), Column(8));
+
+        let tokens: Vec<_> =
+            Lexer::with_source_range(source, start..source.len(), (Line(2), Column(8)))
+                .unwrap()
+                .collect();
+        let snippet: Vec<_> = Lexer::with_source(&source[start..]).collect();
+
+        assert_eq!(snippet.len(), tokens.len());
+        for (absolute, relative) in tokens.iter().zip(&snippet) {
+            assert_eq!(relative.span, absolute.span.relative_to(origin));
+            assert_eq!(absolute.span, relative.span.shift(origin));
+        }
+    }
+
+    #[test]
+    fn test_column_origin() {
+        let tkns: Vec<_> = Lexer::with_source("var x").collect();
+
+        assert_eq!((1, 0), tkns[0].span.caret_position());
+        assert_eq!(3, tkns[0].span.end_col.0);
+        assert_eq!(Span::new(Line(1), Column(0)).grow(2), tkns[0].span);
+        assert_eq!((1, 4), tkns[2].span.caret_position());
+    }
+
+    #[test]
+    fn test_caret_position() {
+        let s = Span::new(Line(10), Column(100));
+        assert_eq!((10, 100), s.caret_position());
+
+        let mut s = Span::new(Line(10), Column(100));
+        s.incr_line();
+        s.incr_col_n(5);
+        assert_eq!((10, 100), s.caret_position());
+    }
+
+    #[test]
+    fn test_contains() {
+        let mut s = Span::new(Line(10), Column(100));
+        s.incr_line();
+        s.incr_col_n(5);
+
+        assert!(s.contains(10, 100));
+        assert!(s.contains(10, 200));
+        assert!(s.contains(11, 4));
+        assert!(!s.contains(10, 99));
+        assert!(!s.contains(11, 5));
+        assert!(!s.contains(12, 0));
+    }
+
+    #[test]
+    fn test_contains_empty() {
+        let mut s = Span::new(Line(10), Column(100));
+        s.complete();
+
+        assert!(!s.contains(10, 101));
+    }
+
+    #[test]
+    fn test_incr_col_saturates() {
+        let mut s = Span::new(Line(10), Column(usize::MAX - 2));
+
+        s.incr_col();
+        assert_eq!(usize::MAX, s.end_col.0);
+
+        s.incr_col();
+        s.incr_col_n(100);
+        assert_eq!(usize::MAX, s.end_col.0);
+        assert_eq!(usize::MAX - 2, s.start_col.0);
+        assert!(s.is_one_line());
+    }
+
+    #[test]
+    fn test_ord() {
+        let a = Span::new(Line(1), Column(5));
+        let b = Span::new(Line(1), Column(5)).grow(1);
+        let c = Span::new(Line(2), Column(0));
+
+        assert!(a < b);
+        assert!(b < c);
+        assert!(Span::new(Line(1), Column(9)) < c);
+    }
+
+    #[test]
+    fn test_grow() {
+        let s = Span::new(Line(10), Column(100)).grow(2);
+
+        assert!(s.is_n_chars(3));
+        assert_eq!(100, s.start_col.0);
+        assert_eq!(103, s.end_col.0);
+    }
+
+    #[test]
+    fn test_narrow() {
+        let s = Span::new(Line(10), Column(100)).grow(4).narrow(2);
+
+        assert!(s.is_n_chars(3));
+        assert_eq!(100, s.start_col.0);
+        assert_eq!(103, s.end_col.0);
+    }
+
+    #[test]
+    fn test_narrow_clamps_at_start() {
+        let s = Span::new(Line(10), Column(100)).narrow(5);
+
+        assert!(s.is_n_chars(0));
+        assert_eq!(100, s.end_col.0);
+    }
+
+    #[test]
+    fn test_narrow_multi_line() {
+        let mut s = Span::new(Line(10), Column(100));
+        s.incr_line();
+        s.incr_col_n(2);
+
+        let s = s.narrow(5);
+        assert_eq!(11, s.end_line.0);
+        assert_eq!(0, s.end_col.0);
+    }
+
+    #[test]
+    fn test_per_line_spans() {
+        let src = "var s = \"one\ntwo lines\nthree\";";
+        let map = Lexer::source_map(src);
+        let tkn = Lexer::with_source(src).nth(6).unwrap();
+        assert!(tkn.span.is_multi_line());
+
+        let spans = tkn.span.per_line_spans(&map);
+        assert_eq!(3, spans.len());
+        assert!(spans.iter().all(|s| s.is_one_line()));
+        assert_eq!([1, 8, 1, 12], spans[0].pack());
+        assert_eq!([2, 0, 2, 9], spans[1].pack());
+        assert_eq!([3, 0, 3, 6], spans[2].pack());
+
+        let s = Span::new(Line(2), Column(3));
+        assert_eq!(vec![s], s.per_line_spans(&map));
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        let mut s = Span::new(Line(10), Column(100));
+        s.incr_line();
+        s.incr_col_n(5);
+
+        let packed = s.pack();
+        assert_eq!([10, 100, 11, 5], packed);
+        assert_eq!(s, Span::unpack(packed));
+    }
+
+    #[test]
+    fn test_pack_saturates() {
+        let s = Span::new(Line(10), Column(u32::MAX as usize + 10));
+
+        let packed = s.pack();
+        assert_eq!([10, u32::MAX, 10, u32::MAX], packed);
+    }
+
     #[test]
     fn test_complete_one_char() {
         let mut s = Span::new(Line(10), Column(100));
@@ -206,4 +560,14 @@ mod tests {
         assert_eq!(10, s.end_line.0);
         assert_eq!(101, s.end_col.0);
     }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the span ends before it starts")]
+    fn test_complete_end_before_start() {
+        let mut s = Span::new(Line(10), Column(100));
+        s.end_col = Column(50);
+
+        s.complete();
+    }
 }
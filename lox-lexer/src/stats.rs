@@ -0,0 +1,70 @@
+use crate::analysis::is_trivia;
+use crate::{Lexeme, LexerIter};
+
+/// The statistics of the tokens of a source, e.g. to report the comment
+/// density or the ratio between code and whitespaces.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LexStats {
+    /// The number of tokens, including the 'eof' token
+    pub tokens: usize,
+    /// The number of significant tokens, which are not trivia
+    pub significant: usize,
+    /// The number of comments
+    pub comments: usize,
+    /// The number of whitespace runs
+    pub whitespaces: usize,
+    /// The number of new lines
+    pub newlines: usize,
+}
+
+impl<'a> LexerIter<'a> {
+    /// Lexes the whole source and counts its tokens.
+    pub fn stats(self) -> LexStats {
+        let mut stats = LexStats::default();
+
+        for tkn in self {
+            stats.tokens += 1;
+
+            match tkn.lexeme {
+                Lexeme::Comment { .. } => stats.comments += 1,
+                Lexeme::Whitespace(_) => stats.whitespaces += 1,
+                Lexeme::NewLine => stats.newlines += 1,
+                ref l if !is_trivia(l) => stats.significant += 1,
+                _ => (),
+            }
+        }
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lexer;
+
+    #[test]
+    fn test_stats() {
+        let source = "// header\nvar x = 1;\n\n  print x; // x\n";
+        let stats = Lexer::with_source(source).stats();
+
+        assert_eq!(
+            LexStats {
+                tokens: 19,
+                significant: 8,
+                comments: 2,
+                whitespaces: 6,
+                newlines: 2,
+            },
+            stats
+        );
+    }
+
+    #[test]
+    fn test_stats_empty() {
+        let stats = Lexer::with_source("").stats();
+
+        assert_eq!(1, stats.tokens);
+        assert_eq!(0, stats.significant);
+    }
+}
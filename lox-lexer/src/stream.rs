@@ -0,0 +1,285 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+
+use crate::chars::*;
+use crate::{Column, LexError, Lexer, Line, SourceMap, Token};
+
+/// The number of characters which must follow a token before it is final:
+/// the lexer looks at most this far past the end of a token, e.g. for the
+/// "." in "1.5", which is read with the number only when a digit follows.
+const LOOKAHEAD: usize = 4;
+
+/// The maximum number of characters read from the source before lexing them.
+const CHUNK: usize = 64;
+
+/// A stream of the tokens of a source which is itself a stream of
+/// characters, e.g. received over an async channel as the user types.
+///
+/// The source is read only when a token is polled, by chunks of at most
+/// 64 characters, so a slow consumer slows down the reading of the source.
+/// A token is returned once enough characters follow it to know that
+/// it is complete; the characters after it stay buffered. A token which
+/// never completes, e.g. an unterminated string, is buffered until the end
+/// of the source. The characters are followed as they are read, so an open
+/// string or comment is not lexed again for each chunk, but only once its
+/// closing character is read.
+///
+/// The stream lexes with the default options. The unexpected characters are
+/// skipped and their errors, as the other errors, are kept by the stream,
+/// see [TokenStream::errors].
+pub struct TokenStream<S> {
+    chars: S,               // the source of characters
+    pending: String,        // the source read but not returned as tokens yet
+    start: (Line, Column),  // the position where the pending source starts
+    ready: VecDeque<Token>, // the complete tokens not returned yet
+    errors: Vec<LexError>,  // the errors of the tokens lexed so far
+    next_id: u32,           // the id of the next complete token
+    scan: Scan,             // where the end of the pending source stands
+    stalled: bool,          // no token was complete when last lexed
+    done: bool,             // the source is exhausted
+    finished: bool,         // the 'eof' token was lexed
+}
+
+/// Where the end of the source read so far stands: in the code, where
+/// `slash` is set after a "/", or in a string or a comment which is still
+/// open, so the token which is being read can not be complete yet.
+#[derive(Clone, Copy, PartialEq)]
+enum Scan {
+    Code { slash: bool },
+    String,
+    Comment,
+}
+
+impl Scan {
+    /// Moves past the next character of the source.
+    fn next(self, c: char) -> Scan {
+        match self {
+            Scan::Code { .. } if c == CHAR_DOUBLE_QUOTE => Scan::String,
+            Scan::Code { slash: true } if c == CHAR_SLASH => Scan::Comment,
+            Scan::Code { .. } => Scan::Code {
+                slash: c == CHAR_SLASH,
+            },
+            Scan::String if c == CHAR_DOUBLE_QUOTE => Scan::Code { slash: false },
+            Scan::Comment if c == CHAR_NEWLINE => Scan::Code { slash: false },
+            open => open,
+        }
+    }
+}
+
+impl<S> TokenStream<S> {
+    /// Returns the errors reported for the source lexed so far, in the
+    /// order of the source, e.g. the [LexError::UnexpectedChar] of the
+    /// characters which are skipped.
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    /// Returns whether the pending source may hold a complete token which was
+    /// not complete when it was last lexed, so it is worth lexing it again.
+    fn should_lex(&self) -> bool {
+        self.done || !self.stalled || matches!(self.scan, Scan::Code { .. })
+    }
+
+    /// Lexes the pending source and moves its complete tokens to the ready
+    /// ones. At the end of the source all the tokens are complete. The ids
+    /// of the tokens lexed from the pending source start at 0, so the ready
+    /// ones get their ids in the whole source.
+    fn lex_pending(&mut self) {
+        let (line, col) = self.start;
+        let output = Lexer::iter_at(&self.pending, line, col).lex_all();
+        let tkns = output.tokens;
+        let mut errors = output.errors;
+
+        if self.done {
+            for mut tkn in tkns {
+                tkn.id = self.next_id;
+                self.next_id = self.next_id.wrapping_add(1);
+                self.ready.push_back(tkn);
+            }
+            self.errors.append(&mut errors);
+            self.pending.clear();
+            self.finished = true;
+            return;
+        }
+
+        // The last token and the 'eof' token can still change.
+        let map = SourceMap::new(&self.pending);
+        let mut cut = 0;
+
+        for mut tkn in tkns.into_iter().rev().skip(2).rev() {
+            let end_line = tkn.span.end_line;
+            let end_col = tkn.span.end_col;
+            let end = map
+                .line_col_to_byte(
+                    end_line.0 - line.0 + 1,
+                    if end_line == line {
+                        end_col.0 - col.0
+                    } else {
+                        end_col.0
+                    },
+                )
+                .unwrap_or(self.pending.len());
+
+            if self.pending[end..].chars().nth(LOOKAHEAD - 1).is_none() {
+                break;
+            }
+
+            tkn.id = self.next_id;
+            self.next_id = self.next_id.wrapping_add(1);
+            self.ready.push_back(tkn);
+            self.start = (end_line, end_col);
+            cut = end;
+        }
+
+        // The errors before the pending source belong to the complete tokens.
+        let (line, col) = self.start;
+        self.errors.extend(errors.into_iter().filter(|err| {
+            let span = err.span();
+            (span.start_line, span.start_col) < (line, col)
+        }));
+
+        self.stalled = cut == 0;
+        self.pending.drain(..cut);
+    }
+}
+
+impl<S: Stream<Item = char> + Unpin> Stream for TokenStream<S> {
+    type Item = Token;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(tkn) = this.ready.pop_front() {
+                return Poll::Ready(Some(tkn));
+            } else if this.finished {
+                return Poll::Ready(None);
+            }
+
+            let mut read = 0;
+            while read < CHUNK && !this.done {
+                match Pin::new(&mut this.chars).poll_next(cx) {
+                    Poll::Ready(Some(c)) => {
+                        this.pending.push(c);
+                        this.scan = this.scan.next(c);
+                        read += 1;
+                    }
+                    Poll::Ready(None) => this.done = true,
+                    Poll::Pending => break,
+                }
+            }
+
+            if read == 0 && !this.done {
+                return Poll::Pending;
+            }
+
+            if this.should_lex() {
+                this.lex_pending();
+            }
+        }
+    }
+}
+
+impl Lexer {
+    /// Returns a stream of the tokens of a stream of characters,
+    /// see [TokenStream] for how the source is read. A stream
+    /// which is not `Unpin` can be pinned with `Box::pin`.
+    pub fn from_stream<S: Stream<Item = char> + Unpin>(chars: S) -> TokenStream<S> {
+        TokenStream {
+            chars,
+            pending: String::new(),
+            start: (Line(1), Column(0)),
+            ready: VecDeque::new(),
+            errors: Vec::new(),
+            next_id: 0,
+            scan: Scan::Code { slash: false },
+            stalled: false,
+            done: false,
+            finished: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::stream::{self, StreamExt};
+
+    fn lex_stream(source: &str) -> Vec<Token> {
+        let chars: Vec<char> = source.chars().collect();
+        block_on(Lexer::from_stream(stream::iter(chars)).collect())
+    }
+
+    #[test]
+    fn test_from_stream() {
+        let source = "var x = \"h\u{e9}\";\nprint x;";
+        let expected: Vec<Token> = Lexer::with_source(source).collect();
+
+        assert_eq!(expected, lex_stream(source));
+    }
+
+    #[test]
+    fn test_from_stream_chunks() {
+        let source = "var longer_name = 1.e+5; // a comment\nprint longer_name..2;\n".repeat(20);
+        let expected: Vec<Token> = Lexer::with_source(&source).collect();
+        let tkns = lex_stream(&source);
+
+        assert_eq!(expected, tkns);
+        assert!(expected.iter().zip(&tkns).all(|(e, t)| e.id() == t.id()));
+    }
+
+    #[test]
+    fn test_from_stream_long_string() {
+        let source = format!("var s = 1;\nprint \"{}\";", "lox ".repeat(5_000));
+        let expected: Vec<Token> = Lexer::with_source(&source).collect();
+
+        assert_eq!(expected, lex_stream(&source));
+    }
+
+    #[test]
+    fn test_from_stream_unterminated_string() {
+        let source = format!("var s = 1;\nprint \"{}", "lox ".repeat(5_000));
+        let chars: Vec<char> = source.chars().collect();
+
+        let mut stream = Lexer::from_stream(stream::iter(chars));
+        let tkns: Vec<Token> = block_on(stream.by_ref().collect());
+        let expected = Lexer::with_source(&source).lex_all();
+
+        assert_eq!(expected.tokens, tkns);
+        assert_eq!(expected.errors, stream.errors());
+        assert!(matches!(
+            stream.errors(),
+            [LexError::UnterminatedString { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_from_stream_errors() {
+        let source = format!(
+            "a @ b;\n\"{}\" # // \"\n{}",
+            "x".repeat(500),
+            "c ".repeat(100)
+        );
+        let chars: Vec<char> = source.chars().collect();
+
+        let mut stream = Lexer::from_stream(stream::iter(chars));
+        let tkns: Vec<Token> = block_on(stream.by_ref().collect());
+        let expected = Lexer::with_source(&source).lex_all();
+
+        assert_eq!(expected.tokens, tkns);
+        assert_eq!(expected.errors, stream.errors());
+        assert_eq!(2, stream.errors().len());
+    }
+
+    #[test]
+    fn test_from_stream_empty() {
+        let tkns = lex_stream("");
+
+        assert_eq!(1, tkns.len());
+        assert!(tkns[0].span.is_eof());
+    }
+}
@@ -0,0 +1,41 @@
+use crate::analysis::is_trivia;
+use crate::Token;
+
+/// Compares two token streams by their significant lexemes, ignoring the
+/// whitespaces, the new lines, the comments and the spans, e.g. in golden
+/// tests which should pass for a reformatted source.
+pub fn tokens_equal_ignoring_trivia(a: &[Token], b: &[Token]) -> bool {
+    let a = a.iter().filter(|tkn| !is_trivia(&tkn.lexeme));
+    let b = b.iter().filter(|tkn| !is_trivia(&tkn.lexeme));
+
+    a.map(|tkn| &tkn.lexeme).eq(b.map(|tkn| &tkn.lexeme))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lexer;
+
+    fn tokens(source: &str) -> Vec<Token> {
+        Lexer::with_source(source).collect()
+    }
+
+    #[test]
+    fn test_tokens_equal_ignoring_trivia() {
+        let a = tokens("var x = 1;\nprint x;");
+        let b = tokens("var   x=1; // one\n\n\tprint x ;");
+
+        assert!(tokens_equal_ignoring_trivia(&a, &b));
+    }
+
+    #[test]
+    fn test_tokens_not_equal_ignoring_trivia() {
+        let a = tokens("var x = 1;\nprint x;");
+
+        assert!(!tokens_equal_ignoring_trivia(
+            &a,
+            &tokens("var y = 1;\nprint y;")
+        ));
+        assert!(!tokens_equal_ignoring_trivia(&a, &tokens("var x = 1;")));
+    }
+}
@@ -1,150 +1,232 @@
+use std::cmp::Ordering;
 use std::fmt::{Debug, Display};
 
-use super::lexeme::Lexeme;
-use super::span::Span;
+use super::escape::EscapePolicy;
+use super::lexeme::{CommentStyle, Lexeme, NumberLit};
+use super::span::{Column, Span};
 
 /// Represents a token generated by the lexer.
 pub struct Token {
     pub(crate) lexeme: Lexeme,
     pub(crate) span: Span,
+    pub(crate) escapes: Option<EscapePolicy>,
+    pub(crate) asi: Option<bool>,
+    pub(crate) source_id: u32,
+    pub(crate) id: u32,
+    pub(crate) closed: Option<bool>,
 }
 
 impl Token {
     pub(crate) fn new(l: Lexeme, s: Span) -> Self {
-        Token { lexeme: l, span: s }
+        Token {
+            lexeme: l,
+            span: s,
+            escapes: None,
+            asi: None,
+            source_id: 0,
+            id: 0,
+            closed: None,
+        }
+    }
+
+    /// Creates a token for a lexeme with a fixed spelling,
+    /// checking that the span has its canonical length.
+    #[inline]
+    pub(crate) fn new_fixed(l: Lexeme, s: Span) -> Self {
+        debug_assert!(l.canonical_len().is_some_and(|n| s.is_n_chars(n)));
+        Self::new(l, s)
+    }
+
+    /// Returns the lexeme of the token.
+    #[inline]
+    pub fn lexeme(&self) -> &Lexeme {
+        &self.lexeme
+    }
+
+    /// Returns the span of the token.
+    #[inline]
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Consumes the token and returns its lexeme, without cloning its payload.
+    #[inline]
+    pub fn into_lexeme(self) -> Lexeme {
+        self.lexeme
+    }
+
+    /// Returns the escape policy which produced a 'string' token, so
+    /// the source can be safely reconstructed. It is `None` for the other tokens.
+    #[inline]
+    pub fn escape_policy(&self) -> Option<EscapePolicy> {
+        self.escapes
+    }
+
+    /// Returns the id of the source the token comes from,
+    /// see [crate::LexerIter::source_id]. It is 0 by default.
+    #[inline]
+    pub fn source_id(&self) -> u32 {
+        self.source_id
+    }
+
+    /// Returns the span of the opening quote of a 'string' token.
+    /// It is `None` for the other tokens.
+    #[inline]
+    pub fn open_quote_span(&self) -> Option<Span> {
+        self.closed
+            .map(|_| Span::new(self.span.start_line, self.span.start_col))
+    }
+
+    /// Returns the span of the closing quote of a 'string' token, e.g. to
+    /// point at where a string is closed. It is `None` for an unterminated
+    /// string and for the other tokens.
+    #[inline]
+    pub fn close_quote_span(&self) -> Option<Span> {
+        self.closed
+            .filter(|closed| *closed)
+            .map(|_| Span::new(self.span.end_line, Column(self.span.end_col.0 - 1)))
+    }
+
+    /// Returns the id of the token, which the lexer assigns in scan order,
+    /// starting at 0 and increasing by one per token, e.g. as a handle which
+    /// does not depend on the position. See [crate::LexerIter::reset].
+    #[inline]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns whether a 'new line' token permits an automatic semicolon
+    /// insertion, when the lexer tags the new lines, see [crate::LexerIter::asi_after].
+    /// It is `None` for the other tokens.
+    #[inline]
+    pub fn permits_asi(&self) -> Option<bool> {
+        self.asi
     }
 
     /// Creates a new 'left parenthesis' token
     #[inline]
     pub fn new_left_parenthesis(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::LeftParen, s)
+        Self::new_fixed(Lexeme::LeftParen, s)
     }
 
     /// Creates a new 'right parenthesis' token
     #[inline]
     pub fn new_right_parenthesis(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::RightParen, s)
+        Self::new_fixed(Lexeme::RightParen, s)
     }
 
     /// Creates a new 'left brace' token
     #[inline]
     pub fn new_left_brace(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::LeftBrace, s)
+        Self::new_fixed(Lexeme::LeftBrace, s)
     }
 
     /// Creates a new 'right brace' token
     #[inline]
     pub fn new_right_brace(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::RightBrace, s)
+        Self::new_fixed(Lexeme::RightBrace, s)
     }
 
     /// Creates a new 'comma' token
     #[inline]
     pub fn new_comma(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::Comma, s)
+        Self::new_fixed(Lexeme::Comma, s)
     }
 
     /// Creates a new 'dot' token.
     #[inline]
     pub fn new_dot(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::Dot, s)
+        Self::new_fixed(Lexeme::Dot, s)
+    }
+
+    /// Creates a new 'dot-dot' token.
+    #[inline]
+    pub fn new_dot_dot(s: Span) -> Self {
+        Self::new_fixed(Lexeme::DotDot, s)
+    }
+
+    /// Creates a new 'ellipsis' token.
+    #[inline]
+    pub fn new_ellipsis(s: Span) -> Self {
+        Self::new_fixed(Lexeme::Ellipsis, s)
     }
 
     /// Creates a new 'plus' token.
     #[inline]
     pub fn new_plus(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::Plus, s)
+        Self::new_fixed(Lexeme::Plus, s)
     }
 
     /// Creates a new 'minus' token.
     #[inline]
     pub fn new_minus(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::Minus, s)
+        Self::new_fixed(Lexeme::Minus, s)
     }
 
     /// Creates a new 'semicolon' token.
     #[inline]
     pub fn new_semicolon(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::Semicolon, s)
+        Self::new_fixed(Lexeme::Semicolon, s)
     }
 
     /// Creates a new 'slash' token.
     #[inline]
     pub fn new_slash(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::Slash, s)
+        Self::new_fixed(Lexeme::Slash, s)
     }
 
     /// Creates a new 'star' token.
     #[inline]
     pub fn new_star(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::Star, s)
+        Self::new_fixed(Lexeme::Star, s)
     }
 
     /// Creates a new 'bang' token.
     #[inline]
     pub fn new_bang(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::Bang, s)
+        Self::new_fixed(Lexeme::Bang, s)
     }
 
     /// Creates a new 'bang-equal' token.
     #[inline]
     pub fn new_bang_equal(s: Span) -> Self {
-        debug_assert!(s.is_two_chars());
-        Self::new(Lexeme::BangEqual, s)
+        Self::new_fixed(Lexeme::BangEqual, s)
     }
 
     /// Creates a new 'equal' token.
     #[inline]
     pub fn new_equal(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::Equal, s)
+        Self::new_fixed(Lexeme::Equal, s)
     }
 
     /// Cteayes a mew 'equal-equal' token.
     #[inline]
     pub fn new_equal_equal(s: Span) -> Self {
-        debug_assert!(s.is_two_chars());
-        Self::new(Lexeme::EqualEqual, s)
+        Self::new_fixed(Lexeme::EqualEqual, s)
     }
 
     /// Creates a new 'greater' token.
     #[inline]
     pub fn new_greater(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::Greater, s)
+        Self::new_fixed(Lexeme::Greater, s)
     }
 
     /// Cteayes a mew 'greater-equal' token.
     #[inline]
     pub fn new_greater_equal(s: Span) -> Self {
-        debug_assert!(s.is_two_chars());
-        Self::new(Lexeme::GreaterEqual, s)
+        Self::new_fixed(Lexeme::GreaterEqual, s)
     }
 
     /// Creates a new 'less' token.
     #[inline]
     pub fn new_less(s: Span) -> Self {
-        debug_assert!(s.is_one_char());
-        Self::new(Lexeme::Less, s)
+        Self::new_fixed(Lexeme::Less, s)
     }
 
     /// Creates a new 'less-equal' token.
     #[inline]
     pub fn new_less_equal(s: Span) -> Self {
-        debug_assert!(s.is_two_chars());
-        Self::new(Lexeme::LessEqual, s)
+        Self::new_fixed(Lexeme::LessEqual, s)
     }
 
     /// Createsa new 'identifier' token.
@@ -153,134 +235,132 @@ impl Token {
         Self::new(Lexeme::Identifier(i.to_string()), s)
     }
 
-    /// Creates a new 'string' token.
+    /// Creates a new 'string' token, closed by its delimiter.
     #[inline]
     pub fn new_string(str: &str, s: Span) -> Self {
-        Self::new(Lexeme::String(str.to_string()), s)
+        let mut t = Self::new(Lexeme::String(str.to_string()), s);
+        t.closed = Some(true);
+        t
     }
 
-    /// Creates a new 'number' token.
+    /// Creates a new 'number' token, with the raw spelling synthesized from the value.
     #[inline]
     pub fn new_number(number: f64, s: Span) -> Self {
-        Self::new(Lexeme::Number(number), s)
+        Self::new(Lexeme::Number(NumberLit::from_value(number)), s)
     }
 
-    /// Creates a new 'comment' token.
+    /// Creates a new 'number' token which keeps the raw source spelling.
+    #[inline]
+    pub fn new_number_with_raw(number: f64, raw: &str, s: Span) -> Self {
+        Self::new(Lexeme::Number(NumberLit::new(number, raw)), s)
+    }
+
+    /// Creates a new line 'comment' token.
     #[inline]
     pub fn new_comment(c: &str, s: Span) -> Self {
-        Self::new(Lexeme::Comment(c.to_string()), s)
+        Self::new(Lexeme::line_comment(c), s)
+    }
+
+    /// Creates a new 'comment' token of a given style.
+    #[inline]
+    pub fn new_comment_with_style(c: &str, style: CommentStyle, s: Span) -> Self {
+        Self::new(Lexeme::comment(c, style), s)
     }
 
     /// Creates a new 'and' token.
     #[inline]
     pub fn new_and(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(3));
-        Self::new(Lexeme::And, s)
+        Self::new_fixed(Lexeme::And, s)
     }
 
     /// Creates a new 'class' token.
     #[inline]
     pub fn new_class(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(5));
-        Self::new(Lexeme::Class, s)
+        Self::new_fixed(Lexeme::Class, s)
     }
 
     /// Creates a new 'else' token.
     #[inline]
     pub fn new_else(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(4));
-        Self::new(Lexeme::Else, s)
+        Self::new_fixed(Lexeme::Else, s)
     }
 
     /// Creates a new 'false' token.
     #[inline]
     pub fn new_false(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(5));
-        Self::new(Lexeme::False, s)
+        Self::new_fixed(Lexeme::False, s)
     }
 
     /// Creates a new 'fun' token.
     #[inline]
     pub fn new_fun(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(3));
-        Self::new(Lexeme::Fun, s)
+        Self::new_fixed(Lexeme::Fun, s)
     }
 
     /// Creates a new 'for' token.
     #[inline]
     pub fn new_for(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(3));
-        Self::new(Lexeme::For, s)
+        Self::new_fixed(Lexeme::For, s)
     }
 
     /// Creates a new 'if' token.
     #[inline]
     pub fn new_if(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(2));
-        Self::new(Lexeme::If, s)
+        Self::new_fixed(Lexeme::If, s)
     }
 
     /// Creates a new 'nil' token.
     #[inline]
     pub fn new_nil(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(3));
-        Self::new(Lexeme::Nil, s)
+        Self::new_fixed(Lexeme::Nil, s)
     }
 
     /// Creates a new 'or' token.
     #[inline]
     pub fn new_or(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(2));
-        Self::new(Lexeme::Or, s)
+        Self::new_fixed(Lexeme::Or, s)
     }
 
     /// Creates a new 'print' token.
     #[inline]
     pub fn new_print(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(5));
-        Self::new(Lexeme::Print, s)
+        Self::new_fixed(Lexeme::Print, s)
     }
 
     /// Creates a new 'return' token.
     #[inline]
     pub fn new_return(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(6));
-        Self::new(Lexeme::Return, s)
+        Self::new_fixed(Lexeme::Return, s)
     }
 
     /// Creates a new 'super' token.
     #[inline]
     pub fn new_super(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(5));
-        Self::new(Lexeme::Super, s)
+        Self::new_fixed(Lexeme::Super, s)
     }
 
     /// Creates a new 'this' token.
     #[inline]
     pub fn new_this(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(4));
-        Self::new(Lexeme::This, s)
+        Self::new_fixed(Lexeme::This, s)
     }
 
     /// Creates a new 'this' token.
     #[inline]
     pub fn new_true(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(4));
-        Self::new(Lexeme::True, s)
+        Self::new_fixed(Lexeme::True, s)
     }
 
     /// Creates a new 'var' token.
     #[inline]
     pub fn new_var(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(3));
-        Self::new(Lexeme::Var, s)
+        Self::new_fixed(Lexeme::Var, s)
     }
 
     /// Creates a new 'while' token.
     #[inline]
     pub fn new_while(s: Span) -> Self {
-        debug_assert!(s.is_n_chars(5));
-        Self::new(Lexeme::While, s)
+        Self::new_fixed(Lexeme::While, s)
     }
 
     /// Creates a new 'whitespace' token.
@@ -296,6 +376,13 @@ impl Token {
         Self::new(Lexeme::NewLine, s)
     }
 
+    /// Creates a new 'bof' token.
+    #[inline]
+    pub fn new_bof(s: Span) -> Self {
+        debug_assert!(s.is_eof());
+        Self::new(Lexeme::Bof, s)
+    }
+
     /// Creates a new 'eof' token.
     #[inline]
     pub fn new_eof(s: Span) -> Self {
@@ -304,6 +391,47 @@ impl Token {
     }
 }
 
+/// Returns the payload of a lexeme as a key for ordering.
+/// A heredoc is ordered by its tag, and then by its body.
+fn payload_key(lexeme: &Lexeme) -> (u64, Option<&str>, Option<&str>) {
+    match lexeme {
+        Lexeme::Number(number) => (number.value.to_bits(), Some(&number.raw), None),
+        Lexeme::Symbol(symbol) => (symbol.0 as u64, None, None),
+        Lexeme::Identifier(s) | Lexeme::String(s) | Lexeme::Regex(s) | Lexeme::Whitespace(s) => {
+            (0, Some(s), None)
+        }
+        Lexeme::Comment { text, style } => (*style as u64, Some(text), None),
+        Lexeme::Heredoc { tag, body } => (0, Some(tag), Some(body)),
+        _ => (0, None, None),
+    }
+}
+
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        self.span == other.span && self.lexeme == other.lexeme
+    }
+}
+
+impl Eq for Token {}
+
+impl PartialOrd for Token {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The tokens are ordered by their span. The ties, e.g. two zero-width
+/// tokens at the same position, are broken by the order of the declaration
+/// of their [crate::LexemeKind], and then by their payload.
+impl Ord for Token {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.span
+            .cmp(&other.span)
+            .then_with(|| self.lexeme.kind().cmp(&other.lexeme.kind()))
+            .then_with(|| payload_key(&self.lexeme).cmp(&payload_key(&other.lexeme)))
+    }
+}
+
 impl Debug for Token {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?} [{:?}]", self.lexeme, self.span)
@@ -316,6 +444,25 @@ impl Display for Token {
     }
 }
 
+/// Prints the tokens one per line, as their debug form, with the spans
+/// aligned in one column after the widest lexeme, e.g. for a debug dump.
+pub fn print_tokens_aligned(tokens: &[Token]) -> String {
+    let lexemes: Vec<String> = tokens.iter().map(|t| format!("{:?}", t.lexeme)).collect();
+    let width = lexemes.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+    let mut out = String::new();
+    for (lexeme, token) in lexemes.iter().zip(tokens) {
+        out.push_str(&format!(
+            "{:<width$} [{:?}]\n",
+            lexeme,
+            token.span,
+            width = width
+        ));
+    }
+
+    out
+}
+
 //
 // Tests
 //
@@ -335,6 +482,16 @@ mod tests {
         assert_eq!(s, t.span);
     }
 
+    #[test]
+    fn test_accessors() {
+        let s = Span::new(Line(10), Column(100)).grow(2);
+        let t = Token::new_identifier("abc", s);
+
+        assert_eq!(&Lexeme::Identifier("abc".to_string()), t.lexeme());
+        assert_eq!(s, t.span());
+        assert_eq!(Lexeme::Identifier("abc".to_string()), t.into_lexeme());
+    }
+
     #[test]
     fn test_new_right_paren() {
         let s = Span::new(Line(10), Column(100));
@@ -375,6 +532,24 @@ mod tests {
         assert_eq!(s, t.span);
     }
 
+    #[test]
+    fn test_new_dot_dot() {
+        let mut s = Span::new(Line(10), Column(100));
+        s.incr_col();
+        let t = Token::new_dot_dot(s);
+        assert_eq!(Lexeme::DotDot, t.lexeme);
+        assert_eq!(s, t.span);
+    }
+
+    #[test]
+    fn test_new_ellipsis() {
+        let mut s = Span::new(Line(10), Column(100));
+        s.incr_col_n(2);
+        let t = Token::new_ellipsis(s);
+        assert_eq!(Lexeme::Ellipsis, t.lexeme);
+        assert_eq!(s, t.span);
+    }
+
     #[test]
     fn test_new_plus() {
         let s = Span::new(Line(10), Column(100));
@@ -512,10 +687,20 @@ mod tests {
         s.incr_col_n(comment.len());
 
         let t = Token::new_comment(comment, s);
-        assert_eq!(Lexeme::Comment(comment.to_string()), t.lexeme);
+        assert_eq!(Lexeme::line_comment(comment), t.lexeme);
         assert_eq!(s, t.span);
     }
 
+    #[test]
+    fn test_new_comment_with_style() {
+        let s = Span::new(Line(1), Column(0)).grow(6);
+
+        let t = Token::new_comment_with_style(" ab ", CommentStyle::Block, s);
+        assert_eq!(Lexeme::comment(" ab ", CommentStyle::Block), t.lexeme);
+        assert_ne!(Token::new_comment(" ab ", s), t);
+        assert_eq!("BLOCK_COMMENT( ab ) [1:0-7]", format!("{:?}", t));
+    }
+
     #[test]
     fn test_new_number() {
         let mut s = Span::new(Line(10), Column(100));
@@ -523,7 +708,17 @@ mod tests {
         s.incr_col_n(2);
 
         let t = Token::new_number(number, s);
-        assert_eq!(Lexeme::Number(number), t.lexeme);
+        assert_eq!(Lexeme::Number(NumberLit::from_value(number)), t.lexeme);
+        assert_eq!(s, t.span);
+    }
+
+    #[test]
+    fn test_new_number_with_raw() {
+        let mut s = Span::new(Line(10), Column(100));
+        s.incr_col_n(3);
+
+        let t = Token::new_number_with_raw(10., "10.0", s);
+        assert_eq!(Lexeme::Number(NumberLit::new(10., "10.0")), t.lexeme);
         assert_eq!(s, t.span);
     }
 
@@ -701,4 +896,80 @@ mod tests {
         assert_eq!(Lexeme::Eof, t.lexeme);
         assert_eq!(s, t.span);
     }
+
+    #[test]
+    fn test_new_bof() {
+        let s = Span::new(Line(1), Column(0)).narrow(1);
+        let t = Token::new_bof(s);
+        assert_eq!(Lexeme::Bof, t.lexeme);
+    }
+
+    #[test]
+    fn test_debug_on_one_line() {
+        let s = Span::new(Line(1), Column(0)).grow(4);
+        let t = Token::new_string("a\nb", s);
+
+        assert_eq!("STRING(a\\nb) [1:0-5]", format!("{:?}", t));
+        assert_eq!(Some("a\nb"), t.lexeme.text());
+    }
+
+    #[test]
+    fn test_ord() {
+        let s1 = Span::new(Line(1), Column(0));
+        let s2 = Span::new(Line(1), Column(1));
+        let mut eof = s2;
+        eof.complete();
+
+        let mut set = std::collections::BTreeSet::new();
+        set.insert(Token::new_eof(eof));
+        set.insert(Token::new_plus(s2));
+        set.insert(Token::new_identifier("b", s1));
+        set.insert(Token::new_identifier("a", s1));
+        set.insert(Token::new(Lexeme::Whitespace(String::new()), eof));
+        set.insert(Token::new_plus(s2));
+
+        let order: Vec<String> = set.iter().map(|t| format!("{:?}", t)).collect();
+        assert_eq!(
+            vec![
+                "IDENTITY(a) [1:0-1]",
+                "IDENTITY(b) [1:0-1]",
+                "PLUS [1:1-2]",
+                "WHITESPACE() [1:2-2]",
+                "EOF [1:2-2]",
+            ],
+            order
+        );
+
+        let heredoc = |tag: &str, body: &str| {
+            let lexeme = Lexeme::Heredoc {
+                tag: tag.to_string(),
+                body: body.to_string(),
+            };
+            Token::new(lexeme, s1)
+        };
+        assert!(heredoc("A", "x\n") < heredoc("B", "x\n"));
+        assert!(heredoc("A", "y\n") < heredoc("B", "x\n"));
+        assert!(heredoc("A", "x\n") < heredoc("A", "y\n"));
+        assert_eq!(
+            Ordering::Equal,
+            heredoc("A", "x\n").cmp(&heredoc("A", "x\n"))
+        );
+    }
+
+    #[test]
+    fn test_print_tokens_aligned() {
+        let tokens = vec![
+            Token::new_identifier("abc", Span::new(Line(1), Column(0)).grow(2)),
+            Token::new_plus(Span::new(Line(1), Column(4))),
+            Token::new_eof(Span::new(Line(1), Column(5)).narrow(1)),
+        ];
+
+        assert_eq!(
+            "IDENTITY(abc) [1:0-3]\n\
+             PLUS          [1:4-5]\n\
+             EOF           [1:5-5]\n",
+            print_tokens_aligned(&tokens)
+        );
+        assert_eq!("", print_tokens_aligned(&[]));
+    }
 }
@@ -0,0 +1,115 @@
+use crate::Lexeme;
+
+/// A visitor over the broad categories of lexemes.
+///
+/// All the methods have a default no-op implementation, so a visitor
+/// only needs to override the categories it is interested in.
+pub trait LexemeVisitor {
+    /// Visits a keyword lexeme (`and`, `class`, `while`, ...).
+    fn visit_keyword(&mut self, _lexeme: &Lexeme) {}
+
+    /// Visits a literal lexeme (identifiers, strings, numbers and regexes).
+    fn visit_literal(&mut self, _lexeme: &Lexeme) {}
+
+    /// Visits an operator or punctuation lexeme (`(`, `+`, `==`, ...).
+    fn visit_operator(&mut self, _lexeme: &Lexeme) {}
+
+    /// Visits a trivia lexeme (whitespaces, new lines and comments).
+    fn visit_trivia(&mut self, _lexeme: &Lexeme) {}
+
+    /// Visits the beginning of file lexeme.
+    fn visit_bof(&mut self, _lexeme: &Lexeme) {}
+
+    /// Visits the end of file lexeme.
+    fn visit_eof(&mut self, _lexeme: &Lexeme) {}
+}
+
+impl Lexeme {
+    /// Dispatches the lexeme to the visitor method of its category.
+    pub fn accept(&self, v: &mut impl LexemeVisitor) {
+        use Lexeme::*;
+
+        match self {
+            LeftParen | RightParen | LeftBrace | RightBrace | Comma | Dot | Minus | Plus
+            | Semicolon | Slash | Star | Bang | BangEqual | Equal | EqualEqual | Greater
+            | GreaterEqual | Less | LessEqual | DotDot | Ellipsis => v.visit_operator(self),
+            Identifier(_) | Symbol(_) | String(_) | Number(_) | Regex(_) | Heredoc { .. } => {
+                v.visit_literal(self)
+            }
+            And | Class | Else | False | Fun | For | If | Nil | Or | Print | Return | Super
+            | This | True | Var | While => v.visit_keyword(self),
+            Comment { .. } | Whitespace(_) | NewLine => v.visit_trivia(self),
+            Bof => v.visit_bof(self),
+            Eof => v.visit_eof(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lexer;
+
+    #[derive(Default)]
+    struct KeywordCounter {
+        keywords: usize,
+    }
+
+    impl LexemeVisitor for KeywordCounter {
+        fn visit_keyword(&mut self, _lexeme: &Lexeme) {
+            self.keywords += 1;
+        }
+    }
+
+    #[derive(Default)]
+    struct CategoryCounter {
+        keywords: usize,
+        literals: usize,
+        operators: usize,
+        trivia: usize,
+        eof: usize,
+    }
+
+    impl LexemeVisitor for CategoryCounter {
+        fn visit_keyword(&mut self, _lexeme: &Lexeme) {
+            self.keywords += 1;
+        }
+
+        fn visit_literal(&mut self, _lexeme: &Lexeme) {
+            self.literals += 1;
+        }
+
+        fn visit_operator(&mut self, _lexeme: &Lexeme) {
+            self.operators += 1;
+        }
+
+        fn visit_trivia(&mut self, _lexeme: &Lexeme) {
+            self.trivia += 1;
+        }
+
+        fn visit_eof(&mut self, _lexeme: &Lexeme) {
+            self.eof += 1;
+        }
+    }
+
+    #[test]
+    fn test_count_keywords() {
+        let mut v = KeywordCounter::default();
+        Lexer::with_source("var x = true;\nif (x) print x;")
+            .for_each(|tkn| tkn.lexeme.accept(&mut v));
+
+        assert_eq!(4, v.keywords);
+    }
+
+    #[test]
+    fn test_count_categories() {
+        let mut v = CategoryCounter::default();
+        Lexer::with_source("var x = 1; // one").for_each(|tkn| tkn.lexeme.accept(&mut v));
+
+        assert_eq!(1, v.keywords);
+        assert_eq!(2, v.literals);
+        assert_eq!(2, v.operators);
+        assert_eq!(5, v.trivia);
+        assert_eq!(1, v.eof);
+    }
+}
@@ -0,0 +1,58 @@
+//! Lexes the `.lox` programs of the `tests/corpus` folder and compares
+//! their token streams, one token per line, with the `.tokens` snapshot
+//! next to each program. Run with `UPDATE_SNAPSHOTS=1` to rewrite the
+//! snapshots after an intended change of the scanner.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lox_lexer::Lexer;
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("corpus")
+}
+
+fn snapshot(source: &str) -> String {
+    Lexer::with_source(source)
+        .map(|tkn| format!("{}\n", tkn))
+        .collect()
+}
+
+#[test]
+fn test_corpus() {
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+
+    let mut programs: Vec<PathBuf> = fs::read_dir(corpus_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect();
+    programs.sort();
+    assert!(!programs.is_empty());
+
+    let mut changed = Vec::new();
+    for program in programs {
+        let source = fs::read_to_string(&program).unwrap();
+        let actual = snapshot(&source);
+
+        let path = program.with_extension("tokens");
+        if update {
+            fs::write(&path, &actual).unwrap();
+            continue;
+        }
+
+        let expected = fs::read_to_string(&path).unwrap_or_default();
+        if actual != expected {
+            changed.push(path.display().to_string());
+        }
+    }
+
+    assert!(
+        changed.is_empty(),
+        "the token streams changed for {:?}, run with UPDATE_SNAPSHOTS=1 if intended",
+        changed
+    );
+}